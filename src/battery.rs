@@ -0,0 +1,99 @@
+/// Length of the windows in which voltage is regressed against current. Short enough for the
+/// state of charge to be roughly constant within a window.
+const REGRESSION_WINDOW: f64 = 5.0;
+/// Minimum spread of the current within a window for a meaningful regression, in A
+const MIN_CURRENT_SPREAD: f64 = 5.0;
+
+#[derive(Clone)]
+pub struct BatteryAnalysis {
+    /// Power drawn at each sample in W
+    pub power: Vec<f32>,
+    /// Charge consumed up to each sample in mAh
+    pub consumed: Vec<f32>,
+    pub total_consumed: f32,
+    /// Energy consumed over the whole flight in Wh
+    pub energy: f32,
+    /// Internal resistance of the pack and wiring in Ω, if the current varied enough
+    pub internal_resistance: Option<f32>,
+    /// Largest drop below the estimated no-load voltage in V
+    pub max_sag: Option<f32>,
+}
+
+/// Least-squares fit of `v = v0 - r * i`, returns (v0, r).
+fn regress(voltage: &[f32], current: &[f32]) -> Option<(f64, f64)> {
+    let n = voltage.len() as f64;
+    let mean_i = current.iter().map(|i| *i as f64).sum::<f64>() / n;
+    let mean_v = voltage.iter().map(|v| *v as f64).sum::<f64>() / n;
+
+    let (min_i, max_i) = current
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), i| {
+            (min.min(*i as f64), max.max(*i as f64))
+        });
+    if max_i - min_i < MIN_CURRENT_SPREAD {
+        return None;
+    }
+
+    let (covariance, variance) =
+        voltage
+            .iter()
+            .zip(current.iter())
+            .fold((0.0, 0.0), |(covariance, variance), (v, i)| {
+                let di = *i as f64 - mean_i;
+                (covariance + di * (*v as f64 - mean_v), variance + di * di)
+            });
+    let slope = covariance / variance;
+    Some((mean_v - slope * mean_i, -slope))
+}
+
+pub fn analyze_battery(times: &[f64], voltage: &[f32], current: &[f32]) -> BatteryAnalysis {
+    let len = times.len().min(voltage.len()).min(current.len());
+    let power: Vec<f32> = voltage[..len]
+        .iter()
+        .zip(current[..len].iter())
+        .map(|(v, i)| v * i)
+        .collect();
+
+    let mut consumed = Vec::with_capacity(len);
+    let mut charge = 0.0f64;
+    let mut energy = 0.0f64;
+    for i in 0..len {
+        if i > 0 {
+            let dt = times[i] - times[i - 1];
+            charge += current[i] as f64 * dt / 3.6;
+            energy += power[i] as f64 * dt / 3600.0;
+        }
+        consumed.push(charge as f32);
+    }
+
+    let mut resistances = Vec::new();
+    let mut max_sag: Option<f32> = None;
+    let mut start = 0;
+    while start < len {
+        let end =
+            start + times[start..len].partition_point(|t| *t < times[start] + REGRESSION_WINDOW);
+        if let Some((v0, r)) = regress(&voltage[start..end], &current[start..end]) {
+            if r > 0.0 {
+                resistances.push(r);
+                let min_voltage = voltage[start..end]
+                    .iter()
+                    .fold(f32::INFINITY, |a, b| a.min(*b));
+                let sag = v0 as f32 - min_voltage;
+                max_sag = Some(max_sag.map_or(sag, |max| max.max(sag)));
+            }
+        }
+        start = end.max(start + 1);
+    }
+
+    resistances.sort_by(|a, b| a.total_cmp(b));
+    let internal_resistance = resistances.get(resistances.len() / 2).map(|r| *r as f32);
+
+    BatteryAnalysis {
+        power,
+        consumed,
+        total_consumed: charge as f32,
+        energy: energy as f32,
+        internal_resistance,
+        max_sag,
+    }
+}
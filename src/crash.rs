@@ -0,0 +1,168 @@
+use std::fmt::Display;
+
+use crate::sensor_clipping::SensorClipping;
+
+/// Acceleration magnitude above which a sample counts as an impact, in g
+const IMPACT_G: f32 = 6.0;
+/// Fraction of the motor output range above which motors count as running
+const MOTORS_RUNNING: f32 = 0.2;
+/// Fraction of the motor output range below which motors count as stopped
+const MOTORS_STOPPED: f32 = 0.02;
+/// Motors stopping within this time after running is a sudden stop rather than a landing
+const SUDDEN_STOP_DURATION: f64 = 0.2;
+/// Signals within this time of each other belong to the same crash candidate
+const CLUSTER_DURATION: f64 = 1.0;
+
+const STANDARD_GRAVITY: f32 = 9.80665;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CrashSignal {
+    Impact,
+    GyroSaturation,
+    MotorStop,
+}
+
+impl Display for CrashSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            Self::Impact => "impact",
+            Self::GyroSaturation => "gyro saturation",
+            Self::MotorStop => "motor stop",
+        };
+        write!(f, "{val}")
+    }
+}
+
+#[derive(Clone)]
+pub struct CrashCandidate {
+    /// Time of the first signal, in seconds
+    pub time: f64,
+    /// Highest acceleration around the candidate, in g
+    pub peak_g: Option<f32>,
+    pub signals: Vec<CrashSignal>,
+}
+
+impl CrashCandidate {
+    /// More than one kind of signal at the same time is very likely an actual crash.
+    pub fn is_likely(&self) -> bool {
+        self.signals.len() > 1
+    }
+}
+
+fn accel_magnitude(accel: [&[f32]; 3], i: usize) -> Option<f32> {
+    let squares: Option<Vec<f32>> = accel.iter().map(|a| a.get(i).map(|v| v * v)).collect();
+    squares.map(|s| s.iter().sum::<f32>().sqrt() / STANDARD_GRAVITY)
+}
+
+/// Times at which all motors dropped from running to stopped within `SUDDEN_STOP_DURATION`, plus
+/// the end of the log if it ends with the motors running, which happens on a disarm in the air.
+fn sudden_motor_stops(times: &[f64], motors: &[&Vec<f32>], output_range: (f32, f32)) -> Vec<f64> {
+    let (min, max) = output_range;
+    let running = min + (max - min) * MOTORS_RUNNING;
+    let stopped = min + (max - min) * MOTORS_STOPPED;
+
+    let len = motors.iter().map(|m| m.len()).fold(times.len(), usize::min);
+    let all_stopped = |i: usize| motors.iter().all(|m| m[i] <= stopped);
+    let any_running = |i: usize| motors.iter().any(|m| m[i] >= running);
+
+    let mut stops = Vec::new();
+    let mut last_running = None;
+    for i in 0..len {
+        if any_running(i) {
+            last_running = Some(i);
+        } else if all_stopped(i) {
+            if let Some(running) = last_running.take() {
+                if times[i] - times[running] <= SUDDEN_STOP_DURATION {
+                    stops.push(times[i]);
+                }
+            }
+        }
+    }
+
+    if len > 0 && last_running == Some(len - 1) {
+        stops.push(times[len - 1]);
+    }
+    stops
+}
+
+/// Finds crash candidates from accelerometer spikes, the raw gyro clipping at its full-scale
+/// range (as found by `detect_sensor_clipping`) and motors suddenly stopping, e.g. by a disarm on
+/// impact. Expects the acceleration in m/s².
+pub fn detect_crashes(
+    times: &[f64],
+    accel: Option<[&[f32]; 3]>,
+    gyro_clipping: Option<&SensorClipping>,
+    motors: Option<&[&Vec<f32>]>,
+    output_range: (f32, f32),
+) -> Vec<CrashCandidate> {
+    let mut signals: Vec<(f64, CrashSignal)> = Vec::new();
+
+    if let Some(accel) = accel {
+        signals.extend(
+            times
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| accel_magnitude(accel, *i).is_some_and(|g| g >= IMPACT_G))
+                .map(|(_, t)| (*t, CrashSignal::Impact)),
+        );
+    }
+
+    if let Some(clipping) = gyro_clipping {
+        // both ends, so that a long span still clusters with what happens at its end
+        signals.extend(
+            clipping
+                .spans
+                .iter()
+                .flat_map(|(start, end)| [*start, *end])
+                .map(|t| (t, CrashSignal::GyroSaturation)),
+        );
+    }
+
+    if let Some(motors) = motors.filter(|m| !m.is_empty()) {
+        signals.extend(
+            sudden_motor_stops(times, motors, output_range)
+                .into_iter()
+                .map(|t| (t, CrashSignal::MotorStop)),
+        );
+    }
+
+    signals.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut candidates: Vec<(CrashCandidate, f64)> = Vec::new();
+    for (time, signal) in signals {
+        match candidates.last_mut() {
+            Some((candidate, last)) if time - *last <= CLUSTER_DURATION => {
+                if !candidate.signals.contains(&signal) {
+                    candidate.signals.push(signal);
+                }
+                *last = time;
+            }
+            _ => candidates.push((
+                CrashCandidate {
+                    time,
+                    peak_g: None,
+                    signals: vec![signal],
+                },
+                time,
+            )),
+        }
+    }
+
+    candidates
+        .into_iter()
+        .map(|(mut candidate, end)| {
+            candidate.peak_g = accel.and_then(|accel| {
+                let start = candidate.time - CLUSTER_DURATION / 2.0;
+                let end = end + CLUSTER_DURATION / 2.0;
+                times
+                    .iter()
+                    .enumerate()
+                    .skip_while(|(_, t)| **t < start)
+                    .take_while(|(_, t)| **t <= end)
+                    .filter_map(|(i, _)| accel_magnitude(accel, i))
+                    .reduce(f32::max)
+            });
+            candidate
+        })
+        .collect()
+}
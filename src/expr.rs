@@ -0,0 +1,650 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::fmt::Display;
+
+/// Error while parsing or evaluating an expression, with the character offset of the
+/// problem for parse errors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExprError {
+    pub message: String,
+    pub position: Option<usize>,
+}
+
+impl ExprError {
+    fn at(position: usize, message: impl ToString) -> Self {
+        Self {
+            message: message.to_string(),
+            position: Some(position),
+        }
+    }
+
+    fn eval(message: impl ToString) -> Self {
+        Self {
+            message: message.to_string(),
+            position: None,
+        }
+    }
+}
+
+impl Display for ExprError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{} (at {})", self.message, position + 1),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+impl Op {
+    fn apply(self, a: f32, b: f32) -> f32 {
+        match self {
+            Self::Add => a + b,
+            Self::Sub => a - b,
+            Self::Mul => a * b,
+            Self::Div => a / b,
+            Self::Pow => a.powf(b),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Abs,
+    Sqrt,
+    Min,
+    Max,
+    Clamp,
+    /// Trailing moving average over a window in ms
+    Avg,
+    /// Derivative per second
+    Deriv,
+    /// PT1 lowpass with a cutoff in Hz
+    Lowpass,
+}
+
+impl Function {
+    const ALL: [(Self, &'static str, usize); 8] = [
+        (Self::Abs, "abs", 1),
+        (Self::Sqrt, "sqrt", 1),
+        (Self::Min, "min", 2),
+        (Self::Max, "max", 2),
+        (Self::Clamp, "clamp", 3),
+        (Self::Avg, "avg", 2),
+        (Self::Deriv, "deriv", 1),
+        (Self::Lowpass, "lowpass", 2),
+    ];
+
+    fn from_name(name: &str) -> Option<(Self, usize)> {
+        Self::ALL
+            .iter()
+            .find(|(_, n, _)| *n == name)
+            .map(|(f, _, arity)| (*f, *arity))
+    }
+}
+
+/// Help text listing the syntax, for showing next to an expression input.
+pub const EXPR_HELP: &str = "Fields by name, e.g. gyroADC[0], or quoted as `name`.\n\
+Operators: + - * / ^ and parentheses.\n\
+abs(x), sqrt(x), min(a, b), max(a, b), clamp(x, lo, hi)\n\
+avg(x, ms): moving average over a window in ms\n\
+deriv(x): change per second\n\
+lowpass(x, hz): PT1 lowpass filter";
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Number(f32),
+    Field(String),
+    Neg(Box<Node>),
+    Binary(Op, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+/// A parsed expression over the fields of a flight.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr {
+    root: Node,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f32),
+    Ident(String),
+    Field(String),
+    Op(Op),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ExprError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '^' => Token::Op(Op::Pow),
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            '`' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|c| *c == '`')
+                    .ok_or_else(|| ExprError::at(start, "unterminated quoted field"))?;
+                let name: String = chars[i + 1..i + 1 + end].iter().collect();
+                i += end + 2;
+                tokens.push((start, Token::Field(name)));
+                continue;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // exponent, e.g. 1e-3
+                if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse()
+                    .map_err(|_| ExprError::at(start, format!("invalid number {}", text)))?;
+                tokens.push((start, Token::Number(number)));
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || "_.".contains(chars[i])) {
+                    i += 1;
+                }
+                // array fields, e.g. motor[3]
+                let mut indexed = false;
+                if i < chars.len() && chars[i] == '[' {
+                    let digits = chars[i + 1..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_digit())
+                        .count();
+                    if digits > 0 && chars.get(i + 1 + digits) == Some(&']') {
+                        i += digits + 2;
+                        indexed = true;
+                    }
+                }
+                let name: String = chars[start..i].iter().collect();
+                let token = if indexed {
+                    Token::Field(name)
+                } else {
+                    Token::Ident(name)
+                };
+                tokens.push((start, token));
+                continue;
+            }
+            c => return Err(ExprError::at(start, format!("unexpected {:?}", c))),
+        };
+        tokens.push((start, token));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|(p, _)| *p)
+            .unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, t)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), ExprError> {
+        let position = self.position();
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(ExprError::at(position, format!("expected {}", description))),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[Op],
+        operand: fn(&mut Self) -> Result<Node, ExprError>,
+    ) -> Result<Node, ExprError> {
+        let mut expr = operand(self)?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if !ops.contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            expr = Node::Binary(op, Box::new(expr), Box::new(operand(self)?));
+        }
+        Ok(expr)
+    }
+
+    fn sum(&mut self) -> Result<Node, ExprError> {
+        self.binary(&[Op::Add, Op::Sub], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Node, ExprError> {
+        self.binary(&[Op::Mul, Op::Div], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Node, ExprError> {
+        if self.peek() == Some(&Token::Op(Op::Sub)) {
+            self.pos += 1;
+            return Ok(Node::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    /// Right associative and binding tighter than negation, so `-x^2` is `-(x^2)`.
+    fn power(&mut self) -> Result<Node, ExprError> {
+        let base = self.atom()?;
+        if self.peek() == Some(&Token::Op(Op::Pow)) {
+            self.pos += 1;
+            return Ok(Node::Binary(
+                Op::Pow,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Node, ExprError> {
+        let position = self.position();
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Node::Number(n)),
+            Some(Token::Field(name)) => Ok(Node::Field(name)),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                let (function, arity) = Function::from_name(&name)
+                    .ok_or_else(|| ExprError::at(position, format!("unknown function {}", name)))?;
+                self.pos += 1;
+                let mut args = vec![self.sum()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    args.push(self.sum()?);
+                }
+                self.expect(Token::RParen, ")")?;
+                if args.len() != arity {
+                    return Err(ExprError::at(
+                        position,
+                        format!("{} takes {} argument(s)", name, arity),
+                    ));
+                }
+                Ok(Node::Call(function, args))
+            }
+            Some(Token::Ident(name)) => Ok(Node::Field(name)),
+            Some(Token::LParen) => {
+                let expr = self.sum()?;
+                self.expect(Token::RParen, ")")?;
+                Ok(expr)
+            }
+            _ => Err(ExprError::at(position, "expected a number, field or (")),
+        }
+    }
+}
+
+/// Fields are borrowed from the flight's columns, only calculated series are owned.
+enum Value<'a> {
+    Scalar(f32),
+    Series(Cow<'a, [f32]>),
+}
+
+impl<'a> Value<'a> {
+    fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        match self {
+            Self::Scalar(v) => Self::Scalar(f(v)),
+            Self::Series(s) => Self::Series(s.iter().map(|v| f(*v)).collect()),
+        }
+    }
+
+    fn zip(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        match (self, other) {
+            (Self::Scalar(a), Self::Scalar(b)) => Self::Scalar(f(a, b)),
+            (Self::Series(a), Self::Scalar(b)) => {
+                Self::Series(a.iter().map(|a| f(*a, b)).collect())
+            }
+            (Self::Scalar(a), Self::Series(b)) => {
+                Self::Series(b.iter().map(|b| f(a, *b)).collect())
+            }
+            (Self::Series(a), Self::Series(b)) => {
+                Self::Series(a.iter().zip(b.iter()).map(|(a, b)| f(*a, *b)).collect())
+            }
+        }
+    }
+
+    fn scalar(&self, what: &str) -> Result<f32, ExprError> {
+        match self {
+            Self::Scalar(v) => Ok(*v),
+            Self::Series(_) => Err(ExprError::eval(format!("{} must be a number", what))),
+        }
+    }
+
+    fn into_series(self, len: usize) -> Cow<'a, [f32]> {
+        match self {
+            Self::Scalar(v) => Cow::Owned(vec![v; len]),
+            Self::Series(s) => s,
+        }
+    }
+}
+
+/// Trailing average over the samples less than `window` seconds before each one, so it stays
+/// right when the log rate varies or frames are missing.
+fn moving_average(values: &[f32], times: &[f64], window: f64) -> Vec<f32> {
+    let mut sum = 0.0f64;
+    let mut start = 0;
+    values
+        .iter()
+        .zip(times.iter())
+        .enumerate()
+        .map(|(i, (v, t))| {
+            sum += *v as f64;
+            while start < i && t - times[start] >= window {
+                sum -= values[start] as f64;
+                start += 1;
+            }
+            (sum / (i + 1 - start) as f64) as f32
+        })
+        .collect()
+}
+
+fn derivative(values: &[f32], times: &[f64]) -> Vec<f32> {
+    std::iter::once(0.0)
+        .chain(values.windows(2).zip(times.windows(2)).map(|(v, t)| {
+            let dt = (t[1] - t[0]) as f32;
+            if dt > 0.0 {
+                (v[1] - v[0]) / dt
+            } else {
+                0.0
+            }
+        }))
+        .collect()
+}
+
+/// PT1 lowpass using the time between each pair of samples, like `derivative`.
+fn lowpass(values: &[f32], cutoff: f32, times: &[f64]) -> Vec<f32> {
+    let rc = 1.0 / (2.0 * PI * cutoff);
+    let mut state = values.first().copied().unwrap_or_default();
+    std::iter::once(state)
+        .chain(values.iter().skip(1).zip(times.windows(2)).map(|(v, t)| {
+            let dt = ((t[1] - t[0]) as f32).max(0.0);
+            state += dt / (rc + dt) * (v - state);
+            state
+        }))
+        .take(values.len())
+        .collect()
+}
+
+impl Expr {
+    pub fn parse(input: &str) -> Result<Self, ExprError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(ExprError::at(0, "empty expression"));
+        }
+
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: input.chars().count(),
+        };
+        let root = parser.sum()?;
+        if parser.peek().is_some() {
+            return Err(ExprError::at(parser.position(), "unexpected input"));
+        }
+        Ok(Self { root })
+    }
+
+    /// Evaluates the expression for every sample, `columns` being series of the same length as
+    /// `times`.
+    pub fn evaluate(
+        &self,
+        columns: &HashMap<String, Vec<f32>>,
+        times: &[f64],
+    ) -> Result<Vec<f32>, ExprError> {
+        let mut values = self
+            .root
+            .eval(columns, times)?
+            .into_series(times.len())
+            .into_owned();
+        values.resize(times.len(), f32::NAN);
+        Ok(values)
+    }
+}
+
+impl Node {
+    fn eval<'a>(
+        &self,
+        columns: &'a HashMap<String, Vec<f32>>,
+        times: &[f64],
+    ) -> Result<Value<'a>, ExprError> {
+        let eval = |node: &Node| node.eval(columns, times);
+        let value = match self {
+            Self::Number(n) => Value::Scalar(*n),
+            Self::Field(name) => columns
+                .get(name)
+                .map(|values| Value::Series(Cow::Borrowed(values)))
+                .ok_or_else(|| ExprError::eval(format!("unknown field {}", name)))?,
+            Self::Neg(expr) => eval(expr)?.map(|v| -v),
+            Self::Binary(op, a, b) => eval(a)?.zip(eval(b)?, |a, b| op.apply(a, b)),
+            Self::Call(function, args) => {
+                let mut args = args.iter().map(eval).collect::<Result<Vec<_>, _>>()?;
+                let arg = args.remove(0);
+                match function {
+                    Function::Abs => arg.map(f32::abs),
+                    Function::Sqrt => arg.map(f32::sqrt),
+                    Function::Min => arg.zip(args.remove(0), f32::min),
+                    Function::Max => arg.zip(args.remove(0), f32::max),
+                    Function::Clamp => {
+                        let low = args[0].scalar("clamp limit")?;
+                        let high = args[1].scalar("clamp limit")?;
+                        if low > high {
+                            return Err(ExprError::eval("clamp limits are the wrong way around"));
+                        }
+                        arg.map(|v| v.clamp(low, high))
+                    }
+                    Function::Avg => {
+                        let window = args[0].scalar("avg window")?;
+                        let values = arg.into_series(times.len());
+                        Value::Series(Cow::Owned(moving_average(
+                            &values,
+                            times,
+                            window as f64 / 1000.0,
+                        )))
+                    }
+                    Function::Deriv => {
+                        Value::Series(Cow::Owned(derivative(&arg.into_series(times.len()), times)))
+                    }
+                    Function::Lowpass => {
+                        let cutoff = args[0].scalar("lowpass cutoff")?;
+                        if cutoff <= 0.0 {
+                            return Err(ExprError::eval("lowpass cutoff must be positive"));
+                        }
+                        let values = arg.into_series(times.len());
+                        Value::Series(Cow::Owned(lowpass(&values, cutoff, times)))
+                    }
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// A named series computed from an expression over the fields of a flight.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivedSeries {
+    pub name: String,
+    /// The expression as entered, for showing and editing it
+    pub expression: String,
+    /// `expression`, parsed once when the series is defined
+    pub expr: Expr,
+}
+
+impl DerivedSeries {
+    /// Evaluates the series, `columns` may contain previously derived series.
+    pub fn evaluate(
+        &self,
+        columns: &HashMap<String, Vec<f32>>,
+        times: &[f64],
+    ) -> Result<Vec<f32>, ExprError> {
+        self.expr.evaluate(columns, times)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(input: &str, columns: &[(&str, Vec<f32>)], times: &[f64]) -> Vec<f32> {
+        let columns: HashMap<String, Vec<f32>> = columns
+            .iter()
+            .map(|(name, values)| (name.to_string(), values.clone()))
+            .collect();
+        Expr::parse(input)
+            .unwrap()
+            .evaluate(&columns, times)
+            .unwrap()
+    }
+
+    fn scalar(input: &str) -> f32 {
+        eval(input, &[], &[0.0])[0]
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(scalar("1 + 2 * 3"), 7.0);
+        assert_eq!(scalar("(1 + 2) * 3"), 9.0);
+        assert_eq!(scalar("10 - 4 - 3"), 3.0);
+        assert_eq!(scalar("8 / 4 / 2"), 1.0);
+        assert_eq!(scalar("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(scalar("2 * 3 ^ 2"), 18.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(scalar("-2 ^ 2"), -4.0);
+        assert_eq!(scalar("(-2) ^ 2"), 4.0);
+        assert_eq!(scalar("2 ^ -1"), 0.5);
+        assert_eq!(scalar("2 * -3"), -6.0);
+        assert_eq!(scalar("--3"), 3.0);
+        assert_eq!(scalar("1 - -1"), 2.0);
+    }
+
+    #[test]
+    fn indexed_and_quoted_fields() {
+        let columns = [
+            ("gyroADC[0]", vec![1.0, 2.0]),
+            ("motor[3]", vec![10.0, 20.0]),
+            ("rc command", vec![3.0, 4.0]),
+        ];
+        let times = [0.0, 0.001];
+        assert_eq!(
+            eval("gyroADC[0] + motor[3]", &columns, &times),
+            [11.0, 22.0]
+        );
+        assert_eq!(eval("`rc command` * 2", &columns, &times), [6.0, 8.0]);
+        assert_eq!(eval("`gyroADC[0]`", &columns, &times), [1.0, 2.0]);
+    }
+
+    #[test]
+    fn unknown_field() {
+        let error = Expr::parse("gyroADC[1]")
+            .unwrap()
+            .evaluate(&HashMap::new(), &[0.0])
+            .unwrap_err();
+        assert_eq!(error.message, "unknown field gyroADC[1]");
+        assert!(Expr::parse("gyroADC[x]").is_err());
+    }
+
+    #[test]
+    fn arity() {
+        let error = Expr::parse("1 + abs(1, 2)").unwrap_err();
+        assert_eq!(error.message, "abs takes 1 argument(s)");
+        assert_eq!(error.position, Some(4));
+        assert!(Expr::parse("min(1)").is_err());
+        assert!(Expr::parse("clamp(1, 2)").is_err());
+        assert!(Expr::parse("lowpass(1, 2, 3)").is_err());
+        assert!(Expr::parse("foo(1)").is_err());
+        assert!(Expr::parse("clamp(1, 0, 2)").is_ok());
+    }
+
+    // 1, 2, 3 and 4 ms between the samples
+    const UNEVEN_TIMES: [f64; 5] = [0.0, 0.001, 0.003, 0.006, 0.010];
+
+    #[test]
+    fn avg_uneven_timestamps() {
+        let columns = [(
+            "x",
+            UNEVEN_TIMES.iter().map(|t| *t as f32 * 1000.0).collect(),
+        )];
+        let avg = eval("avg(x, 2.5)", &columns, &UNEVEN_TIMES);
+        assert_close(&avg, &[0.0, 0.5, 2.0, 6.0, 10.0]);
+    }
+
+    #[test]
+    fn deriv_uneven_timestamps() {
+        let columns = [(
+            "x",
+            UNEVEN_TIMES.iter().map(|t| *t as f32 * 1000.0).collect(),
+        )];
+        let deriv = eval("deriv(x)", &columns, &UNEVEN_TIMES);
+        assert_close(&deriv, &[0.0, 1000.0, 1000.0, 1000.0, 1000.0]);
+    }
+
+    #[test]
+    fn lowpass_uneven_timestamps() {
+        // a time constant of 1ms
+        let cutoff = 1.0 / (2.0 * PI * 0.001);
+        let columns = [("x", vec![0.0, 1.0, 1.0, 1.0, 1.0])];
+        let filtered = eval(&format!("lowpass(x, {})", cutoff), &columns, &UNEVEN_TIMES);
+        assert_close(&filtered, &[0.0, 0.5, 0.833_333, 0.958_333, 0.991_667]);
+    }
+}
@@ -0,0 +1,298 @@
+use std::ops::Range;
+
+use crate::filters::Biquad;
+use crate::flight_data::FlightData;
+use crate::iter::values_in_ranges;
+use crate::step_response::excited_windows;
+use crate::utils::{median, rms};
+
+/// Setpoint changes further apart than this are pauses in the stick movement, not packets
+const MAX_PACKET_INTERVAL: f64 = 0.05;
+/// Minimum number of packet intervals for a packet rate estimate
+const MIN_PACKET_INTERVALS: usize = 50;
+/// FF jitter is everything above `packet rate / JITTER_CUTOFF_DIVIDER`
+const JITTER_CUTOFF_DIVIDER: f64 = 8.0;
+const MIN_JITTER_CUTOFF: f64 = 10.0;
+/// Spikes deviate from the smoothed FF by `SPIKE_FACTOR` times the jitter RMS, and at least
+/// `SPIKE_MIN_DEVIATION`
+const SPIKE_FACTOR: f32 = 4.0;
+const SPIKE_MIN_DEVIATION: f32 = 20.0;
+/// Longest lead or lag of FF against the gyro that is searched for, in seconds
+const MAX_LEAD: f64 = 0.05;
+const LEAD_WINDOW_DURATION: f64 = 1.0;
+/// Windows with less FF than this are not used for the lead
+const LEAD_MIN_FF: f32 = 10.0;
+
+/// Feedforward settings from the headers, `None` for settings missing in the log.
+pub struct FeedforwardSettings {
+    pub jitter_factor: Option<u8>,
+    pub smooth_factor: Option<u8>,
+    pub averaging: Option<u8>,
+    pub boost: Option<u8>,
+    pub max_rate_limit: Option<u8>,
+}
+
+impl FeedforwardSettings {
+    pub fn from_headers(fd: &FlightData) -> Self {
+        Self {
+            jitter_factor: fd.header_value("feedforward_jitter_factor"),
+            smooth_factor: fd.header_value("feedforward_smooth_factor"),
+            averaging: fd.header_value("feedforward_averaging"),
+            boost: fd.header_value("feedforward_boost"),
+            max_rate_limit: fd.header_value("feedforward_max_rate_limit"),
+        }
+    }
+
+    pub fn averaging_name(&self) -> Option<&'static str> {
+        match self.averaging? {
+            0 => Some("Off"),
+            1 => Some("2 point"),
+            2 => Some("3 point"),
+            3 => Some("4 point"),
+            _ => None,
+        }
+    }
+}
+
+pub struct AxisFeedforward {
+    /// Correlation of FF with the setpoint derivative, from -1 to 1
+    pub correlation: Option<f32>,
+    /// RMS of the FF content above the jitter cutoff
+    pub jitter: f32,
+    /// Jitter in % of the FF RMS
+    pub jitter_ratio: f32,
+    /// Times of FF spikes right after a setpoint step from the RC link
+    pub spikes: Vec<f64>,
+    /// How far FF leads the gyro in seconds, negative if it lags behind
+    pub lead: Option<f64>,
+    /// |FF| during spikes, zero otherwise
+    pub indicator: Vec<f32>,
+}
+
+pub struct FeedforwardAnalysis {
+    /// RC link packet rate in Hz, as seen in the steps of the setpoint
+    pub packet_rate: Option<f64>,
+    pub jitter_cutoff: f64,
+    pub axes: [AxisFeedforward; 3],
+}
+
+/// When RC packets arrived, as found in `packet_arrivals`, and what follows from their rate
+struct PacketTiming {
+    arrivals: Vec<usize>,
+    /// Samples per packet interval
+    samples: usize,
+    jitter_cutoff: f64,
+}
+
+/// Indices of the samples within `ranges` at which the setpoint changed on any axis, i.e. a new
+/// RC packet arrived.
+fn packet_arrivals(setpoint: [&[f32]; 3], ranges: &[Range<usize>]) -> Vec<usize> {
+    let len = setpoint.iter().map(|s| s.len()).min().unwrap_or(0);
+    ranges
+        .iter()
+        .flat_map(|range| range.start.max(1)..range.end.min(len))
+        .filter(|i| setpoint.iter().any(|s| s[*i] != s[i - 1]))
+        .collect()
+}
+
+/// Estimates the RC link packet rate from the median interval between setpoint steps.
+fn detect_packet_rate(times: &[f64], arrivals: &[usize]) -> Option<f64> {
+    let intervals: Vec<f64> = arrivals
+        .windows(2)
+        .filter_map(|w| {
+            let interval = times.get(w[1])? - times.get(w[0])?;
+            (interval > 0.0 && interval < MAX_PACKET_INTERVAL).then_some(interval)
+        })
+        .collect();
+
+    if intervals.len() < MIN_PACKET_INTERVALS {
+        return None;
+    }
+
+    median(intervals).map(|interval| 1.0 / interval)
+}
+
+/// Zero-phase lowpass, so the smoothed FF doesn't lag behind the raw one
+fn smooth(data: &[f32], cutoff: f64, sample_rate: f64) -> Vec<f32> {
+    let cutoff = f64::min(cutoff, sample_rate * 0.45);
+    let mut forward = Biquad::lowpass(cutoff, sample_rate);
+    let mut smoothed: Vec<f32> = data
+        .iter()
+        .map(|x| forward.apply(*x as f64) as f32)
+        .collect();
+
+    let mut backward = Biquad::lowpass(cutoff, sample_rate);
+    for x in smoothed.iter_mut().rev() {
+        *x = backward.apply(*x as f64) as f32;
+    }
+    smoothed
+}
+
+/// Correlation of FF with the setpoint derivative over one packet interval, which is what FF is
+/// calculated from, over the samples within `ranges`.
+fn derivative_correlation(
+    setpoint: &[f32],
+    ff: &[f32],
+    packet_samples: usize,
+    ranges: &[Range<usize>],
+) -> Option<f32> {
+    let len = usize::min(setpoint.len(), ff.len());
+    let (mut sum_fd, mut sum_ff, mut sum_dd) = (0.0f64, 0.0f64, 0.0f64);
+    for i in ranges
+        .iter()
+        .flat_map(|range| range.start.max(packet_samples)..range.end.min(len))
+    {
+        let derivative = (setpoint[i] - setpoint[i - packet_samples]) as f64;
+        let f = ff[i] as f64;
+        sum_fd += f * derivative;
+        sum_ff += f * f;
+        sum_dd += derivative * derivative;
+    }
+
+    let denominator = (sum_ff * sum_dd).sqrt();
+    (denominator > 0.0).then(|| (sum_fd / denominator) as f32)
+}
+
+/// Offset of the gyro response behind FF in one window, from the peak of the cross-correlation of
+/// FF with the first difference of the gyro.
+fn window_lead(ff: &[f32], gyro: &[f32], sample_rate: f64) -> Option<f64> {
+    let max_lag = (MAX_LEAD * sample_rate) as isize;
+    let dg: Vec<f32> = gyro.windows(2).map(|w| w[1] - w[0]).collect();
+    let len = usize::min(ff.len(), dg.len()) as isize;
+    if len <= 2 * max_lag + 2 {
+        return None;
+    }
+
+    let correlation: Vec<f64> = (-max_lag..=max_lag)
+        .map(|lag| {
+            let (ff_start, dg_start) = if lag >= 0 { (0, lag) } else { (-lag, 0) };
+            let overlap = (len - lag.abs()) as usize;
+            ff[ff_start as usize..]
+                .iter()
+                .zip(dg[dg_start as usize..].iter())
+                .take(overlap)
+                .map(|(f, g)| (*f as f64) * (*g as f64))
+                .sum::<f64>()
+                / overlap as f64
+        })
+        .collect();
+
+    let (peak, value) = correlation
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    (*value > 0.0 && value.is_finite()).then(|| (peak as isize - max_lag) as f64 / sample_rate)
+}
+
+fn feedforward_lead(
+    ff: &[f32],
+    gyro: &[f32],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> Option<f64> {
+    let window_size = (sample_rate * LEAD_WINDOW_DURATION) as usize;
+    let len = usize::min(ff.len(), gyro.len());
+    let leads = excited_windows(&ff[..len], ranges, window_size, LEAD_MIN_FF)
+        .filter_map(|range| window_lead(&ff[range.clone()], &gyro[range], sample_rate))
+        .collect();
+    median(leads)
+}
+
+fn analyze_axis(
+    times: &[f64],
+    setpoint: &[f32],
+    ff: &[f32],
+    gyro: &[f32],
+    timing: &PacketTiming,
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> AxisFeedforward {
+    let packet_samples = timing.samples;
+    let smoothed = smooth(ff, timing.jitter_cutoff, sample_rate);
+    let deviation: Vec<f32> = ff.iter().zip(smoothed.iter()).map(|(f, s)| f - s).collect();
+    let jitter = rms(values_in_ranges(&deviation, ranges).into_iter()).unwrap_or_default();
+    let ff_rms = rms(values_in_ranges(ff, ranges).into_iter()).unwrap_or_default();
+    let threshold = f32::max(SPIKE_FACTOR * jitter, SPIKE_MIN_DEVIATION);
+
+    let mut spikes = Vec::new();
+    let mut indicator = vec![0.0; ff.len()];
+    let mut last_spike = None;
+    for arrival in timing.arrivals.iter().copied() {
+        // a spike belongs to the step if it peaks within one packet interval after it
+        let end = usize::min(arrival + packet_samples, deviation.len());
+        let Some(peak) =
+            (arrival..end).max_by(|a, b| deviation[*a].abs().total_cmp(&deviation[*b].abs()))
+        else {
+            continue;
+        };
+        if deviation[peak].abs() < threshold
+            || last_spike.is_some_and(|last| peak < last + packet_samples)
+        {
+            continue;
+        }
+
+        last_spike = Some(peak);
+        spikes.push(times[peak]);
+        for (indicator, ff) in indicator[arrival..end]
+            .iter_mut()
+            .zip(ff[arrival..end].iter())
+        {
+            *indicator = ff.abs();
+        }
+    }
+
+    AxisFeedforward {
+        correlation: derivative_correlation(setpoint, ff, packet_samples, ranges),
+        jitter,
+        jitter_ratio: if ff_rms > 0.0 {
+            100.0 * jitter / ff_rms
+        } else {
+            0.0
+        },
+        spikes,
+        lead: feedforward_lead(ff, gyro, sample_rate, ranges),
+        indicator,
+    }
+}
+
+/// Analyzes how cleanly FF follows the sticks within `ranges`: jitter, spikes from RC link steps
+/// and how far it leads the gyro.
+pub fn analyze_feedforward(
+    times: &[f64],
+    setpoint: [&[f32]; 3],
+    ff: [&[f32]; 3],
+    gyro: [&[f32]; 3],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> FeedforwardAnalysis {
+    let arrivals = packet_arrivals(setpoint, ranges);
+    let packet_rate = detect_packet_rate(times, &arrivals);
+    let timing = PacketTiming {
+        arrivals,
+        samples: packet_rate
+            .map(|rate| (sample_rate / rate).round() as usize)
+            .unwrap_or(1)
+            .max(1),
+        jitter_cutoff: packet_rate
+            .map(|rate| f64::max(rate / JITTER_CUTOFF_DIVIDER, MIN_JITTER_CUTOFF))
+            .unwrap_or(MIN_JITTER_CUTOFF),
+    };
+
+    let axes = [0, 1, 2].map(|axis| {
+        analyze_axis(
+            times,
+            setpoint[axis],
+            ff[axis],
+            gyro[axis],
+            &timing,
+            sample_rate,
+            ranges,
+        )
+    });
+
+    FeedforwardAnalysis {
+        packet_rate,
+        jitter_cutoff: timing.jitter_cutoff,
+        axes,
+    }
+}
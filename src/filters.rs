@@ -0,0 +1,76 @@
+use std::f64::consts::PI;
+
+/// Second order IIR filter, coefficients from the RBJ audio EQ cookbook.
+#[derive(Clone, Copy)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl Biquad {
+    fn from_coefficients(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    /// Returns `(cos(w0), alpha)` for a butterworth response at `cutoff`.
+    fn prewarp(cutoff: f64, sample_rate: f64) -> (f64, f64) {
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * std::f64::consts::FRAC_1_SQRT_2))
+    }
+
+    pub fn lowpass(cutoff: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = Self::prewarp(cutoff, sample_rate);
+        let b1 = 1.0 - cos;
+        Self::from_coefficients(
+            [b1 / 2.0, b1, b1 / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    pub fn highpass(cutoff: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = Self::prewarp(cutoff, sample_rate);
+        let b1 = -(1.0 + cos);
+        Self::from_coefficients(
+            [-b1 / 2.0, b1, -b1 / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    pub fn apply(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Filters `data` to the band between `low` and `high` Hz with a highpass/lowpass cascade.
+pub fn band_pass(data: &[f32], sample_rate: f64, low: f64, high: f64) -> Vec<f32> {
+    let high = f64::min(high, sample_rate * 0.45);
+    let mut highpass = Biquad::highpass(low, sample_rate);
+    let mut lowpass = Biquad::lowpass(high, sample_rate);
+    data.iter()
+        .map(|x| lowpass.apply(highpass.apply(*x as f64)) as f32)
+        .collect()
+}
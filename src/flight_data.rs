@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::ops::Range;
+use std::str::FromStr;
 use std::sync::mpsc::Sender;
 
 use blackbox_log::frame::Frame;
@@ -8,7 +10,24 @@ use blackbox_log::headers::Firmware;
 use blackbox_log::headers::PwmProtocol;
 use blackbox_log::units::FlagSet;
 
+use crate::battery::{analyze_battery, BatteryAnalysis};
+use crate::crash::{detect_crashes, CrashCandidate};
+use crate::expr::DerivedSeries;
+use crate::flight_phases::{segment_flight, FlightPhase, FlightPhases};
 use crate::gui::blackbox_ui_ext::*;
+use crate::gui::colors::Colors;
+use crate::sensor_clipping::{detect_sensor_clipping, ClippingAnalysis, SensorRanges};
+
+/// Scale factors from the configured D gain to the D term output, see `DTERM_SCALE` in
+/// Betaflight's pid.h and `FP_PID_RATE_D_MULTIPLIER` in INAV's pid.h respectively.
+const BETAFLIGHT_DTERM_SCALE: f32 = 0.000529;
+const INAV_DTERM_SCALE: f32 = 1.0 / 1905.0;
+
+const PID_HEADERS: [&str; 3] = ["rollPID", "pitchPID", "yawPID"];
+
+/// Motor output ranges used when the log doesn't contain a `motorOutput` header
+const DIGITAL_MOTOR_OUTPUT_RANGE: (f32, f32) = (48.0, 2047.0);
+const ANALOG_MOTOR_OUTPUT_RANGE: (f32, f32) = (1000.0, 2000.0);
 
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -25,6 +44,11 @@ pub struct FlightData {
     pub times: Vec<f64>,
     pub main_values: HashMap<String, Vec<f32>>,
     pub main_units: HashMap<String, String>,
+    pub dterm_unfiltered: [Option<Vec<f32>>; 3],
+    pub battery: Option<BatteryAnalysis>,
+    pub phases: FlightPhases,
+    pub crashes: Vec<CrashCandidate>,
+    pub sensor_clipping: Option<ClippingAnalysis>,
 }
 
 impl FlightData {
@@ -89,7 +113,7 @@ impl FlightData {
             i = (i + 1) % 1000;
         }
 
-        Ok(Self {
+        let mut flight_data = Self {
             index,
             firmware: headers.firmware(),
             firmware_date: headers
@@ -114,7 +138,171 @@ impl FlightData {
             times,
             main_values,
             main_units,
-        })
+            dterm_unfiltered: Default::default(),
+            battery: None,
+            phases: FlightPhases::default(),
+            crashes: Vec::new(),
+            sensor_clipping: None,
+        };
+        flight_data.dterm_unfiltered = flight_data.reconstruct_dterm();
+        flight_data.battery = flight_data
+            .battery_voltage()
+            .zip(flight_data.amperage())
+            .map(|(voltage, current)| analyze_battery(&flight_data.times, voltage, current));
+        flight_data.phases = flight_data.segment_phases();
+        let sensor_clipping = detect_sensor_clipping(
+            &flight_data.times,
+            flight_data.gyro_unfiltered().map(|g| g.map(|g| &g[..])),
+            flight_data.accel().map(|a| a.map(|a| &a[..])),
+            SensorRanges::from_headers(&flight_data),
+        );
+        flight_data.crashes = detect_crashes(
+            &flight_data.times,
+            flight_data.accel().map(|a| a.map(|a| &a[..])),
+            sensor_clipping.gyro.as_ref(),
+            flight_data.motor().as_deref(),
+            flight_data.motor_output_range(),
+        );
+        flight_data.sensor_clipping = Some(sensor_clipping);
+
+        Ok(flight_data)
+    }
+
+    fn segment_phases(&self) -> FlightPhases {
+        let Some(setpoint) = self.setpoint() else {
+            return FlightPhases::default();
+        };
+
+        segment_flight(
+            &self.times,
+            setpoint[3],
+            self.gyro_filtered().map(|g| g.map(|g| &g[..])),
+            self.accel().map(|a| a.map(|a| &a[..])),
+            self.sample_rate(),
+        )
+    }
+
+    /// Sample ranges of the `selected` phases, for restricting analyses to them. The ranges are
+    /// kept apart rather than joined, so analyses don't see steps between them that never
+    /// happened. With all phases selected, this is the whole flight.
+    pub fn phase_ranges(&self, selected: &[FlightPhase]) -> Vec<Range<usize>> {
+        if FlightPhase::ALL
+            .iter()
+            .all(|phase| selected.contains(phase))
+        {
+            std::iter::once(0..self.times.len()).collect()
+        } else {
+            self.phases.ranges(selected)
+        }
+    }
+
+    /// Copy of the flight with the `derived` series added to the main values. They are evaluated
+    /// in order, so later series can use earlier ones. Returns the names of the series that
+    /// couldn't be evaluated along with the reason.
+    pub fn with_derived_series(&self, derived: &[DerivedSeries]) -> (Self, Vec<(String, String)>) {
+        let mut flight_data = self.clone();
+        let mut errors = Vec::new();
+        for (i, series) in derived.iter().enumerate() {
+            if self.main_values.contains_key(&series.name) {
+                errors.push((series.name.clone(), "name of a logged field".to_string()));
+                continue;
+            }
+            if derived[..i].iter().any(|s| s.name == series.name) {
+                errors.push((
+                    series.name.clone(),
+                    "name of another derived series".to_string(),
+                ));
+                continue;
+            }
+
+            match series.evaluate(&flight_data.main_values, &flight_data.times) {
+                Ok(values) => {
+                    flight_data.main_values.insert(series.name.clone(), values);
+                }
+                Err(e) => errors.push((series.name.clone(), e.to_string())),
+            }
+        }
+        (flight_data, errors)
+    }
+
+    /// Reconstructs the D term before filtering from the derivative of the unfiltered gyro,
+    /// the same way the firmware calculates it from the (separately filtered) gyro. Axes
+    /// without a D gain are `None`, same as for `d`.
+    fn reconstruct_dterm(&self) -> [Option<Vec<f32>>; 3] {
+        let scale = match self.firmware {
+            Firmware::Betaflight(_) => BETAFLIGHT_DTERM_SCALE,
+            Firmware::Inav(_) => INAV_DTERM_SCALE,
+        };
+        let log_rate = self.log_rate() as f32;
+
+        (0..3)
+            .map(|axis| {
+                let gyro = self.gyro_unfiltered()?[axis];
+                let gain = self.pid_gains(axis)?.get(2).copied()?;
+                if gain == 0.0 {
+                    return None;
+                }
+
+                let factor = -gain * scale * log_rate;
+                let dterm = std::iter::once(0.0)
+                    .chain(gyro.windows(2).map(|w| (w[1] - w[0]) * factor))
+                    .collect();
+                Some(dterm)
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap()
+    }
+
+    /// Parses a comma-separated header that isn't handled by blackbox_log itself.
+    pub fn header_values<T: FromStr>(&self, name: &str) -> Option<Vec<T>> {
+        self.unknown_headers
+            .get(name)?
+            .split(',')
+            .map(|v| v.trim().parse().ok())
+            .collect()
+    }
+
+    pub fn header_value<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.unknown_headers.get(name)?.trim().parse().ok()
+    }
+
+    /// P, I, D (and on some firmwares FF) gains for an axis.
+    pub fn pid_gains(&self, axis: usize) -> Option<Vec<f32>> {
+        self.header_values(PID_HEADERS.get(axis)?)
+    }
+
+    /// Frequency of the PID loop in Hz, if the headers contain it.
+    pub fn pid_frequency(&self) -> Option<f64> {
+        let looptime = self.header_value::<f64>("looptime")?;
+        let denom = self.header_value::<f64>("pid_process_denom").unwrap_or(1.0);
+        (looptime > 0.0).then(|| 1_000_000.0 / (looptime * denom))
+    }
+
+    /// Rate at which main frames should be logged according to the loop rate and the logging
+    /// interval in the headers.
+    pub fn configured_log_rate(&self) -> Option<f64> {
+        let interval =
+            self.unknown_headers
+                .get("P interval")
+                .and_then(|v| match v.split_once('/') {
+                    Some((num, denom)) => {
+                        Some(num.trim().parse::<f64>().ok()? / denom.trim().parse::<f64>().ok()?)
+                    }
+                    None => Some(1.0 / v.trim().parse::<f64>().ok()?),
+                });
+
+        self.pid_frequency()
+            .zip(interval)
+            .map(|(freq, interval)| freq * interval)
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+    }
+
+    /// Rate at which main frames were logged, derived from the loop rate and the logging
+    /// interval in the headers. Falls back to the measured `sample_rate`.
+    pub fn log_rate(&self) -> f64 {
+        self.configured_log_rate()
+            .unwrap_or_else(|| self.sample_rate())
     }
 
     fn get_vector_series<const N: usize>(&self, series_name: &str) -> Option<[&Vec<f32>; N]> {
@@ -161,6 +349,10 @@ impl FlightData {
             .unwrap()
     }
 
+    pub fn d_unfiltered(&self) -> [Option<&Vec<f32>>; 3] {
+        [0, 1, 2].map(|i| self.dterm_unfiltered[i].as_ref())
+    }
+
     pub fn f(&self) -> Option<[&Vec<f32>; 3]> {
         self.get_vector_series("axisF")
     }
@@ -176,6 +368,30 @@ impl FlightData {
             .collect::<Option<Vec<_>>>()
     }
 
+    /// Lowest and highest value the motor outputs can take, as logged in `motor`.
+    pub fn motor_output_range(&self) -> (f32, f32) {
+        if let Some([min, max]) = self
+            .header_values::<f32>("motorOutput")
+            .and_then(|v| <[f32; 2]>::try_from(v).ok())
+        {
+            return (min, max);
+        }
+
+        match self.esc_protocol {
+            PwmProtocol::Dshot150
+            | PwmProtocol::Dshot300
+            | PwmProtocol::Dshot600
+            | PwmProtocol::Dshot1200
+            | PwmProtocol::Proshot1000 => DIGITAL_MOTOR_OUTPUT_RANGE,
+            _ => (
+                self.header_value("minthrottle")
+                    .unwrap_or(ANALOG_MOTOR_OUTPUT_RANGE.0),
+                self.header_value("maxthrottle")
+                    .unwrap_or(ANALOG_MOTOR_OUTPUT_RANGE.1),
+            ),
+        }
+    }
+
     pub fn electrical_rpm(&self) -> Option<Vec<&Vec<f32>>> {
         let erpm_count = self
             .main_values
@@ -187,6 +403,19 @@ impl FlightData {
             .collect::<Option<Vec<_>>>()
     }
 
+    /// Mean rotation frequency of all motors in Hz, calculated from the logged eRPM / 100.
+    pub fn motor_frequency(&self) -> Option<Vec<f32>> {
+        let erpm = self.electrical_rpm().filter(|erpm| !erpm.is_empty())?;
+        let pole_pairs = self.header_value::<f32>("motor_poles").unwrap_or(14.0) / 2.0;
+        let scale = 100.0 / 60.0 / pole_pairs / (erpm.len() as f32);
+
+        Some(
+            (0..self.times.len())
+                .map(|i| erpm.iter().map(|motor| motor[i]).sum::<f32>() * scale)
+                .collect(),
+        )
+    }
+
     pub fn battery_voltage(&self) -> Option<&Vec<f32>> {
         self.main_values.get("vbatLatest")
     }
@@ -209,8 +438,10 @@ impl FlightData {
             .take(NUM_SAMPLES)
             .collect();
         samples.sort();
-        let sample_interval = samples[NUM_SAMPLES / 2];
-        let rate = 1_000_000.0 / (sample_interval as f64);
+        let Some(sample_interval) = samples.get(samples.len() / 2) else {
+            return 0.0;
+        };
+        let rate = 1_000_000.0 / (*sample_interval as f64);
         (rate / 100.0).round() * 100.0
     }
 
@@ -243,6 +474,87 @@ impl FlightData {
                     ui.label("");
                 }
                 ui.end_row();
+
+                if let Some(battery) = &self.battery {
+                    ui.label("Battery");
+                    ui.label(format!(
+                        "{:.0}mAh, {:.1}Wh",
+                        battery.total_consumed, battery.energy
+                    ));
+                    ui.end_row();
+
+                    if let Some((sag, resistance)) =
+                        battery.max_sag.zip(battery.internal_resistance)
+                    {
+                        ui.label("Sag");
+                        ui.label(format!("{:.2}V, ~{:.0}mΩ", sag, resistance * 1000.0));
+                        ui.end_row();
+                    }
+                }
+
+                if let Some(clipping) = self.sensor_clipping.as_ref().filter(|c| c.any()) {
+                    let colors = Colors::get(ui);
+                    ui.label("Clipping");
+                    ui.vertical(|ui| {
+                        let sensors = [
+                            (
+                                "Gyro",
+                                &clipping.gyro,
+                                format!("±{:.0}°/s", clipping.ranges.gyro),
+                            ),
+                            (
+                                "Acc",
+                                &clipping.accel,
+                                format!("±{:.0}g", clipping.ranges.accel),
+                            ),
+                        ];
+                        for (name, sensor, range) in sensors {
+                            let Some(sensor) = sensor.as_ref().filter(|s| s.samples > 0) else {
+                                continue;
+                            };
+                            ui.colored_label(
+                                colors.sensor_clipping,
+                                format!(
+                                    "⚠ {}: {}× ({:.2}s)",
+                                    name,
+                                    sensor.spans.len(),
+                                    sensor.duration()
+                                ),
+                            )
+                            .on_hover_text(format!(
+                                "{} samples at the {} range",
+                                sensor.samples, range
+                            ));
+                        }
+                    });
+                    ui.end_row();
+                }
+
+                if !self.crashes.is_empty() {
+                    let start = self.times.first().copied().unwrap_or_default();
+                    ui.label("Crash");
+                    ui.vertical(|ui| {
+                        for crash in self.crashes.iter() {
+                            let signals = crash
+                                .signals
+                                .iter()
+                                .map(|s| s.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            let text = match crash.peak_g {
+                                Some(g) => format!("{:.1}s, {:.1}g", crash.time - start, g),
+                                None => format!("{:.1}s", crash.time - start),
+                            };
+                            let text = if crash.is_likely() {
+                                egui::RichText::new(format!("💥 {}", text)).strong()
+                            } else {
+                                egui::RichText::new(format!("❓ {}", text))
+                            };
+                            ui.label(text).on_hover_text(signals);
+                        }
+                    });
+                    ui.end_row();
+                }
             });
 
         false
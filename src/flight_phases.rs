@@ -0,0 +1,274 @@
+use std::fmt::Display;
+use std::ops::Range;
+
+/// Length of the windows that are classified, in seconds
+const WINDOW_DURATION: f64 = 0.25;
+/// Phases shorter than this are merged into the preceding one
+const MIN_PHASE_DURATION: f64 = 0.5;
+/// Duration after leaving and before returning to the ground counted as takeoff and landing
+const TAKEOFF_LANDING_DURATION: f64 = 2.0;
+
+/// On the ground, the throttle (0–1000) and gyro (°/s) stay below these
+const GROUND_THROTTLE: f32 = 80.0;
+const GROUND_GYRO_RATE: f32 = 30.0;
+/// On the ground the accelerometer reads 1g, give or take this much
+const GROUND_ACCEL_TOLERANCE: f32 = 0.3;
+/// Hovering, the gyro stays below this rate and the throttle varies by less than this std
+const HOVER_GYRO_RATE: f32 = 60.0;
+const HOVER_THROTTLE_STD: f32 = 30.0;
+/// Rotating faster than this is an aggressive manoeuvre (flips, rolls, snaps)
+const AGGRESSIVE_GYRO_RATE: f32 = 360.0;
+
+const STANDARD_GRAVITY: f32 = 9.80665;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlightPhase {
+    Idle,
+    Takeoff,
+    Hover,
+    Cruise,
+    Aggressive,
+    Landing,
+}
+
+impl FlightPhase {
+    pub const ALL: [Self; 6] = [
+        Self::Idle,
+        Self::Takeoff,
+        Self::Hover,
+        Self::Cruise,
+        Self::Aggressive,
+        Self::Landing,
+    ];
+}
+
+impl Display for FlightPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = match self {
+            Self::Idle => "Idle",
+            Self::Takeoff => "Takeoff",
+            Self::Hover => "Hover",
+            Self::Cruise => "Cruise",
+            Self::Aggressive => "Aggressive",
+            Self::Landing => "Landing",
+        };
+        write!(f, "{val}")
+    }
+}
+
+/// A continuous stretch of one phase, with the sample indices `start..end`.
+#[derive(Clone)]
+pub struct PhaseSegment {
+    pub phase: FlightPhase,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Default)]
+pub struct FlightPhases {
+    pub segments: Vec<PhaseSegment>,
+}
+
+impl FlightPhases {
+    /// Per sample, whether it belongs to one of the `selected` phases.
+    pub fn mask(&self, selected: &[FlightPhase]) -> Vec<bool> {
+        let len = self.segments.last().map(|s| s.end).unwrap_or_default();
+        let mut mask = vec![false; len];
+        for segment in self.segments.iter().filter(|s| selected.contains(&s.phase)) {
+            mask[segment.start..segment.end].fill(true);
+        }
+        mask
+    }
+
+    /// Sample ranges of the segments in the `selected` phases, with adjacent segments joined.
+    pub fn ranges(&self, selected: &[FlightPhase]) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for segment in self.segments.iter().filter(|s| selected.contains(&s.phase)) {
+            match ranges.last_mut() {
+                Some(last) if last.end == segment.start => last.end = segment.end,
+                _ => ranges.push(segment.start..segment.end),
+            }
+        }
+        ranges
+    }
+
+    /// Total time spent in `phase`, in seconds.
+    pub fn duration(&self, times: &[f64], phase: FlightPhase) -> f64 {
+        self.segments
+            .iter()
+            .filter(|s| s.phase == phase && s.end > s.start)
+            .map(|s| times[s.end - 1] - times[s.start])
+            .sum()
+    }
+}
+
+struct Window {
+    throttle_mean: f32,
+    throttle_std: f32,
+    max_gyro_rate: f32,
+    /// Mean magnitude of the acceleration in g
+    accel: Option<f32>,
+}
+
+impl Window {
+    fn classify(&self) -> FlightPhase {
+        let accel_on_ground = self
+            .accel
+            .map(|a| (a - 1.0).abs() < GROUND_ACCEL_TOLERANCE)
+            .unwrap_or(true);
+        if self.throttle_mean < GROUND_THROTTLE
+            && self.max_gyro_rate < GROUND_GYRO_RATE
+            && accel_on_ground
+        {
+            FlightPhase::Idle
+        } else if self.max_gyro_rate >= AGGRESSIVE_GYRO_RATE {
+            FlightPhase::Aggressive
+        } else if self.max_gyro_rate < HOVER_GYRO_RATE && self.throttle_std < HOVER_THROTTLE_STD {
+            FlightPhase::Hover
+        } else {
+            FlightPhase::Cruise
+        }
+    }
+}
+
+fn window_stats(
+    range: Range<usize>,
+    throttle: &[f32],
+    gyro: Option<[&[f32]; 3]>,
+    accel: Option<[&[f32]; 3]>,
+) -> Window {
+    let len = range.len() as f32;
+    let throttle = &throttle[range.clone()];
+    let throttle_mean = throttle.iter().sum::<f32>() / len;
+    let throttle_std = (throttle
+        .iter()
+        .map(|t| (t - throttle_mean).powi(2))
+        .sum::<f32>()
+        / len)
+        .sqrt();
+
+    let max_gyro_rate = gyro
+        .map(|gyro| {
+            gyro.iter()
+                .flat_map(|g| g[range.clone()].iter())
+                .fold(0.0f32, |max, g| f32::max(max, g.abs()))
+        })
+        .unwrap_or_default();
+
+    let accel = accel.map(|accel| {
+        range
+            .clone()
+            .map(|i| accel.iter().map(|a| a[i].powi(2)).sum::<f32>().sqrt())
+            .sum::<f32>()
+            / len
+            / STANDARD_GRAVITY
+    });
+
+    Window {
+        throttle_mean,
+        throttle_std,
+        max_gyro_rate,
+        accel,
+    }
+}
+
+/// Relabels runs shorter than `min_windows` with the phase before them (or after them, at the
+/// start of the flight), so a single calm moment in a cruise doesn't become a hover.
+fn merge_short_runs(phases: &mut [FlightPhase], min_windows: usize) {
+    let mut start = 0;
+    while start < phases.len() {
+        let end = (start..phases.len())
+            .find(|i| phases[*i] != phases[start])
+            .unwrap_or(phases.len());
+
+        if end - start < min_windows {
+            let replacement = if start > 0 {
+                Some(phases[start - 1])
+            } else {
+                phases.get(end).copied()
+            };
+            if let Some(replacement) = replacement {
+                phases[start..end].fill(replacement);
+            }
+        }
+
+        start = end;
+    }
+}
+
+/// Marks the first and last `windows` of every stretch in the air as takeoff and landing.
+fn mark_takeoff_and_landing(phases: &mut [FlightPhase], windows: usize) {
+    for i in 1..phases.len() {
+        let (before, after) = (phases[i - 1], phases[i]);
+        if before == FlightPhase::Idle && after != FlightPhase::Idle {
+            for phase in phases[i..]
+                .iter_mut()
+                .take(windows)
+                .take_while(|p| **p != FlightPhase::Idle)
+            {
+                *phase = FlightPhase::Takeoff;
+            }
+        } else if before != FlightPhase::Idle && after == FlightPhase::Idle {
+            for phase in phases[..i]
+                .iter_mut()
+                .rev()
+                .take(windows)
+                .take_while(|p| **p != FlightPhase::Idle)
+            {
+                *phase = FlightPhase::Landing;
+            }
+        }
+    }
+}
+
+/// Splits the flight into phases, classifying short windows by throttle, gyro rates and the
+/// accelerometer. Throttle is expected from 0 to 1000, the acceleration in m/s².
+pub fn segment_flight(
+    times: &[f64],
+    throttle: &[f32],
+    gyro: Option<[&[f32]; 3]>,
+    accel: Option<[&[f32]; 3]>,
+    sample_rate: f64,
+) -> FlightPhases {
+    let len = gyro
+        .iter()
+        .chain(accel.iter())
+        .flat_map(|series| series.iter().map(|s| s.len()))
+        .fold(usize::min(times.len(), throttle.len()), usize::min);
+    if len == 0 || sample_rate <= 0.0 {
+        return FlightPhases::default();
+    }
+
+    let window = usize::max(1, (WINDOW_DURATION * sample_rate) as usize);
+    let windows: Vec<_> = (0..len)
+        .step_by(window)
+        .map(|start| start..usize::min(start + window, len))
+        .collect();
+
+    let mut phases: Vec<FlightPhase> = windows
+        .iter()
+        .map(|range| window_stats(range.clone(), throttle, gyro, accel).classify())
+        .collect();
+    merge_short_runs(
+        &mut phases,
+        (MIN_PHASE_DURATION / WINDOW_DURATION).ceil() as usize,
+    );
+    mark_takeoff_and_landing(
+        &mut phases,
+        (TAKEOFF_LANDING_DURATION / WINDOW_DURATION).round() as usize,
+    );
+
+    let mut segments: Vec<PhaseSegment> = Vec::new();
+    for (range, phase) in windows.into_iter().zip(phases) {
+        match segments.last_mut() {
+            Some(last) if last.phase == phase => last.end = range.end,
+            _ => segments.push(PhaseSegment {
+                phase,
+                start: range.start,
+                end: range.end,
+            }),
+        }
+    }
+
+    FlightPhases { segments }
+}
@@ -1,8 +1,11 @@
 pub mod blackbox_ui_ext;
 pub mod colors;
+pub mod derived_series;
+pub mod export;
 pub mod flex;
 pub mod flight_view;
 pub mod open_file;
+pub mod phase_strip;
 pub mod tabs;
 
 use std::path::PathBuf;
@@ -120,10 +123,12 @@ impl eframe::App for App {
 
                     ui.separator();
 
-                    const TABS: [FlightViewTab; 3] = [
+                    const TABS: [FlightViewTab; 5] = [
                         FlightViewTab::Plot,
                         FlightViewTab::Tune,
                         FlightViewTab::Vibe,
+                        FlightViewTab::Pilot,
+                        FlightViewTab::Timing,
                     ];
                     for tab in TABS.into_iter() {
                         let label = if narrow {
@@ -183,6 +188,19 @@ impl eframe::App for App {
                                             ui.label("⚠ Flight ");
                                         }
                                         ui.monospace(format!("#{}", i + 1));
+                                        if parse_result
+                                            .as_ref()
+                                            .is_ok_and(|f| f.crashes.iter().any(|c| c.is_likely()))
+                                        {
+                                            ui.colored_label(colors.error, "💥")
+                                                .on_hover_text("Likely crash in this flight");
+                                        }
+                                        if parse_result.as_ref().is_ok_and(|f| {
+                                            f.sensor_clipping.as_ref().is_some_and(|c| c.any())
+                                        }) {
+                                            ui.colored_label(colors.sensor_clipping, "✂")
+                                                .on_hover_text("Gyro or accelerometer clipping");
+                                        }
 
                                         if parse_result.is_ok() {
                                             ui.with_layout(
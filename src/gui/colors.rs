@@ -18,6 +18,7 @@ const YELLOW: Color32 = Color32::from_rgb(0xd7, 0x99, 0x21);
 const PURPLE: Color32 = Color32::from_rgb(0xb1, 0x62, 0x86);
 const AQUA: Color32 = Color32::from_rgb(0x68, 0x9d, 0x6a);
 const ORANGE: Color32 = Color32::from_rgb(0xd6, 0x5d, 0x0e);
+const GRAY: Color32 = Color32::from_rgb(0x92, 0x83, 0x74);
 
 const RED_LIGHT: Color32 = Color32::from_rgb(0xfb, 0x49, 0x34);
 const GREEN_LIGHT: Color32 = Color32::from_rgb(0xb8, 0xbb, 0x26);
@@ -49,6 +50,14 @@ const COLORS_DARK_MODE: Colors = Colors {
         BLUE_LIGHT,
         FG_DARK_MODE,
     ],
+    phases: [
+        GRAY,
+        AQUA_LIGHT,
+        BLUE_LIGHT,
+        GREEN_LIGHT,
+        RED_LIGHT,
+        PURPLE_LIGHT,
+    ],
 
     gyro_unfiltered: RED,
     gyro_filtered: RED_LIGHT,
@@ -56,11 +65,26 @@ const COLORS_DARK_MODE: Colors = Colors {
     p: GREEN_LIGHT,
     i: BLUE_LIGHT,
     d: ORANGE_LIGHT,
+    d_unfiltered: ORANGE,
     f: YELLOW,
+    propwash: PURPLE_LIGHT,
+    tracking_error: AQUA_LIGHT,
+    iterm_windup: BLUE,
+    ff_spikes: YELLOW_LIGHT,
+    vibration: AQUA_LIGHT,
+    coherence: BLUE_LIGHT,
+    rates: ORANGE_LIGHT,
+    unreliable: RED,
+    saturation: YELLOW,
+    clipping: RED,
+    sensor_clipping: RED_LIGHT,
 
     voltage: BLUE_LIGHT,
     current: RED_LIGHT,
+    power: YELLOW_LIGHT,
+    consumed: PURPLE_LIGHT,
     rssi: AQUA_LIGHT,
+    loop_timing: BLUE_LIGHT,
 
     error: RED,
     selected: ORANGE_LIGHT,
@@ -80,6 +104,14 @@ const COLORS_LIGHT_MODE: Colors = Colors {
         BLUE_DARK,
         FG_LIGHT_MODE,
     ],
+    phases: [
+        GRAY,
+        AQUA_DARK,
+        BLUE_DARK,
+        GREEN_DARK,
+        RED_DARK,
+        PURPLE_DARK,
+    ],
 
     gyro_unfiltered: RED,
     gyro_filtered: RED_DARK,
@@ -87,11 +119,26 @@ const COLORS_LIGHT_MODE: Colors = Colors {
     p: GREEN_DARK,
     i: BLUE_DARK,
     d: ORANGE_DARK,
+    d_unfiltered: ORANGE,
     f: YELLOW,
+    propwash: PURPLE_DARK,
+    tracking_error: AQUA_DARK,
+    iterm_windup: BLUE,
+    ff_spikes: YELLOW_DARK,
+    vibration: AQUA_DARK,
+    coherence: BLUE_DARK,
+    rates: ORANGE_DARK,
+    unreliable: RED,
+    saturation: YELLOW,
+    clipping: RED,
+    sensor_clipping: RED_DARK,
 
     voltage: BLUE_DARK,
     current: RED_DARK,
+    power: YELLOW_DARK,
+    consumed: PURPLE_DARK,
     rssi: AQUA_DARK,
+    loop_timing: BLUE_DARK,
 
     error: RED,
     selected: ORANGE_DARK,
@@ -102,6 +149,8 @@ pub struct Colors {
     pub triple_secondary: [Color32; 3],
     pub quad: [Color32; 4],
     pub motors: [Color32; 8],
+    /// Indexed like `FlightPhase::ALL`
+    pub phases: [Color32; 6],
 
     pub gyro_unfiltered: Color32,
     pub gyro_filtered: Color32,
@@ -109,11 +158,26 @@ pub struct Colors {
     pub p: Color32,
     pub i: Color32,
     pub d: Color32,
+    pub d_unfiltered: Color32,
     pub f: Color32,
+    pub propwash: Color32,
+    pub tracking_error: Color32,
+    pub iterm_windup: Color32,
+    pub ff_spikes: Color32,
+    pub vibration: Color32,
+    pub coherence: Color32,
+    pub rates: Color32,
+    pub unreliable: Color32,
+    pub saturation: Color32,
+    pub clipping: Color32,
+    pub sensor_clipping: Color32,
 
     pub voltage: Color32,
     pub current: Color32,
+    pub power: Color32,
+    pub consumed: Color32,
     pub rssi: Color32,
+    pub loop_timing: Color32,
 
     pub error: Color32,
     pub selected: Color32,
@@ -0,0 +1,120 @@
+use crate::expr::{DerivedSeries, Expr, EXPR_HELP};
+use crate::gui::colors::Colors;
+
+/// Derived series are defined once for the session and applied to every flight.
+fn derived_series_id() -> egui::Id {
+    egui::Id::new("derived_series")
+}
+
+pub fn derived_series(ctx: &egui::Context) -> Vec<DerivedSeries> {
+    ctx.data(|d| d.get_temp(derived_series_id()))
+        .unwrap_or_default()
+}
+
+fn set_derived_series(ctx: &egui::Context, series: Vec<DerivedSeries>) {
+    ctx.data_mut(|d| d.insert_temp(derived_series_id(), series));
+}
+
+/// Editor for the derived series, with the errors of evaluating them for the current flight.
+#[derive(Default)]
+pub struct DerivedSeriesEditor {
+    name: String,
+    expression: String,
+}
+
+impl DerivedSeriesEditor {
+    pub fn show(&mut self, ui: &mut egui::Ui, errors: &[(String, String)], pending: bool) {
+        let colors = Colors::get(ui);
+        let ctx = ui.ctx().clone();
+        let mut series = derived_series(&ctx);
+        let mut changed = false;
+
+        egui::CollapsingHeader::new(format!("ƒ Derived Series ({})", series.len())).show(
+            ui,
+            |ui| {
+                if !series.is_empty() {
+                    let mut removed = None;
+                    egui::Grid::new(ui.next_auto_id())
+                        .num_columns(4)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (i, derived) in series.iter().enumerate() {
+                                ui.monospace(&derived.name);
+                                ui.monospace(&derived.expression);
+                                match errors.iter().find(|(name, _)| *name == derived.name) {
+                                    Some((_, error)) => {
+                                        ui.colored_label(colors.error, format!("⚠ {}", error));
+                                    }
+                                    None if pending => {
+                                        ui.spinner();
+                                    }
+                                    None => {
+                                        ui.label("");
+                                    }
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("✏").on_hover_text("Edit").clicked() {
+                                        self.name = derived.name.clone();
+                                        self.expression = derived.expression.clone();
+                                    }
+                                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                        removed = Some(i);
+                                    }
+                                });
+                                ui.end_row();
+                            }
+                        });
+                    if let Some(i) = removed {
+                        series.remove(i);
+                        changed = true;
+                    }
+                }
+
+                let parsed = Expr::parse(&self.expression);
+                let name = self.name.trim().to_string();
+                let exists = series.iter().any(|s| s.name == name);
+                ui.horizontal_wrapped(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.name)
+                            .hint_text("Name")
+                            .desired_width(120.0),
+                    );
+                    ui.label("=");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.expression)
+                            .hint_text("e.g. setpoint[0] - gyroADC[0]")
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(300.0),
+                    );
+                    let label = if exists { "✔ Update" } else { "➕ Add" };
+                    let clicked = ui
+                        .add_enabled(!name.is_empty() && parsed.is_ok(), egui::Button::new(label))
+                        .clicked();
+                    if let (true, Ok(expr)) = (clicked, &parsed) {
+                        let derived = DerivedSeries {
+                            name: name.clone(),
+                            expression: self.expression.trim().to_string(),
+                            expr: expr.clone(),
+                        };
+                        match series.iter_mut().find(|s| s.name == name) {
+                            Some(existing) => *existing = derived,
+                            None => series.push(derived),
+                        }
+                        changed = true;
+                        self.name.clear();
+                        self.expression.clear();
+                    }
+                    ui.label("❓").on_hover_text(EXPR_HELP);
+                });
+
+                if let (Err(e), false) = (&parsed, self.expression.trim().is_empty()) {
+                    ui.colored_label(colors.error, format!("⚠ {}", e));
+                }
+            },
+        );
+
+        if changed {
+            set_derived_series(&ctx, series);
+        }
+    }
+}
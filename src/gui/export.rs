@@ -0,0 +1,15 @@
+/// Buttons to copy CSV data to the clipboard and, on native, to save it to a file. `csv` is
+/// only called when one of them is clicked.
+pub fn csv_export_buttons(ui: &mut egui::Ui, file_name: &str, csv: impl Fn() -> String) {
+    if ui.button("📋 Copy CSV").clicked() {
+        ui.output_mut(|o| o.copied_text = csv());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if ui.button("💾 Save CSV").clicked() {
+        crate::utils::save_file(file_name.to_string(), csv());
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    let _ = file_name;
+}
@@ -23,6 +23,16 @@ impl<'a> FlexLayout<'a> {
         self
     }
 
+    pub fn add_enabled<F>(mut self, enabled: bool, cb: F) -> Self
+    where
+        F: FnOnce(&mut egui::Ui) -> egui::Response + 'a,
+    {
+        if enabled {
+            self.cbs.push(Box::new(cb));
+        }
+        self
+    }
+
     pub fn show(self, ui: &mut egui::Ui) -> egui::Response {
         ui.add(self)
     }
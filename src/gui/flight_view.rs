@@ -1,28 +1,115 @@
+use std::sync::mpsc::channel;
 use std::sync::Arc;
 
 use egui_oszi::TimeseriesGroup;
 
+use crate::expr::DerivedSeries;
 use crate::flight_data::FlightData;
+use crate::flight_phases::FlightPhase;
+use crate::gui::derived_series::{derived_series, DerivedSeriesEditor};
+use crate::gui::phase_strip::show_phase_strip;
 use crate::gui::tabs::*;
+use crate::utils::{execute_in_background, BackgroundCompStore};
+
+type DerivedFlightData = (Arc<FlightData>, Vec<(String, String)>);
 
 pub struct FlightView {
+    ctx: egui::Context,
+    /// The flight as parsed from the log
+    parsed: Arc<FlightData>,
+    /// The flight including the derived series
+    data: Arc<FlightData>,
+    derived: Vec<DerivedSeries>,
+    derived_update: Option<BackgroundCompStore<DerivedFlightData>>,
+    derived_errors: Vec<(String, String)>,
+    derived_editor: DerivedSeriesEditor,
+    selected_phases: Vec<FlightPhase>,
     plot_group: TimeseriesGroup,
     plot_tab: PlotTab,
     tune_tab: TuneTab,
     vibe_tab: VibeTab,
+    pilot_tab: PilotTab,
+    timing_tab: TimingTab,
 }
 
 impl FlightView {
     pub fn new(ctx: &egui::Context, data: Arc<FlightData>) -> Self {
+        let ranges = data.phase_ranges(&FlightPhase::ALL);
         Self {
+            ctx: ctx.clone(),
+            selected_phases: FlightPhase::ALL.to_vec(),
             plot_tab: PlotTab::new(data.clone()),
-            tune_tab: TuneTab::new(data.clone()),
-            vibe_tab: VibeTab::new(ctx, data),
-            plot_group: TimeseriesGroup::new("timeseries_plots", false),
+            tune_tab: TuneTab::new(data.clone(), ranges.clone()),
+            vibe_tab: VibeTab::new(ctx, data.clone(), ranges.clone()),
+            pilot_tab: PilotTab::new(ctx, data.clone(), ranges),
+            timing_tab: TimingTab::new(data.clone()),
+            plot_group: TimeseriesGroup::new(TIMESERIES_GROUP_ID, false),
+            parsed: data.clone(),
+            data,
+            derived: Vec::new(),
+            derived_update: None,
+            derived_errors: Vec::new(),
+            derived_editor: DerivedSeriesEditor::default(),
+        }
+    }
+
+    /// Evaluates the derived series in the background, see `process_derived_series`.
+    fn update_derived_series(&mut self) {
+        let (sender, receiver) = channel();
+        let parsed = self.parsed.clone();
+        let derived = self.derived.clone();
+        let ctx = self.ctx.clone();
+        execute_in_background(async move {
+            let (data, errors) = if derived.is_empty() {
+                (parsed, Vec::new())
+            } else {
+                let (data, errors) = parsed.with_derived_series(&derived);
+                (Arc::new(data), errors)
+            };
+            let _ = sender.send((data, errors));
+            ctx.request_repaint();
+        });
+        self.derived_update = Some(BackgroundCompStore::new(receiver));
+    }
+
+    fn process_derived_series(&mut self) {
+        let derived = derived_series(&self.ctx);
+        if derived != self.derived {
+            self.derived = derived;
+            self.update_derived_series();
         }
+
+        let Some(update) = self.derived_update.as_mut() else {
+            return;
+        };
+        let Some((data, errors)) = update.get().clone() else {
+            return;
+        };
+        self.derived_update = None;
+        self.data = data;
+        self.derived_errors = errors;
+        // only the plot and vibe tabs can show arbitrary fields
+        self.plot_tab.set_flight_data(self.data.clone());
+        self.vibe_tab.set_flight_data(&self.ctx, self.data.clone());
+    }
+
+    /// Restricts the analysis tabs to the selected phases. The plot and timing tabs always show
+    /// the whole flight.
+    fn restrict_to_phases(&mut self) {
+        let ranges = self.data.phase_ranges(&self.selected_phases);
+        self.tune_tab.set_ranges(ranges.clone());
+        self.vibe_tab.set_ranges(ranges.clone());
+        self.pilot_tab.set_ranges(ranges);
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui, tab: FlightViewTab) {
+        self.process_derived_series();
+        self.derived_editor
+            .show(ui, &self.derived_errors, self.derived_update.is_some());
+        if show_phase_strip(ui, &self.data, &mut self.selected_phases) {
+            self.restrict_to_phases();
+        }
+
         ui.vertical(|ui| match tab {
             FlightViewTab::Plot => {
                 egui::ScrollArea::vertical()
@@ -30,6 +117,8 @@ impl FlightView {
             }
             FlightViewTab::Tune => self.tune_tab.show(ui, &mut self.plot_group),
             FlightViewTab::Vibe => self.vibe_tab.show(ui),
+            FlightViewTab::Pilot => self.pilot_tab.show(ui),
+            FlightViewTab::Timing => self.timing_tab.show(ui, &mut self.plot_group),
         });
     }
 }
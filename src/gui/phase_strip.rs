@@ -0,0 +1,132 @@
+use egui::{Color32, Rect, Sense, Stroke, Vec2};
+
+use crate::flight_data::FlightData;
+use crate::flight_phases::FlightPhase;
+use crate::gui::colors::Colors;
+
+const STRIP_HEIGHT: f32 = 12.0;
+
+fn phase_color(colors: &Colors, phase: FlightPhase) -> Color32 {
+    let index = FlightPhase::ALL.iter().position(|p| *p == phase).unwrap();
+    colors.phases[index]
+}
+
+/// Coloured strip of the flight phases over the whole flight, with toggles for restricting the
+/// analyses to some of them. Returns whether the selection changed.
+pub fn show_phase_strip(
+    ui: &mut egui::Ui,
+    fd: &FlightData,
+    selected: &mut Vec<FlightPhase>,
+) -> bool {
+    let colors = Colors::get(ui);
+    let times = &fd.times;
+    let (Some(first), Some(last)) = (times.first(), times.last()) else {
+        return false;
+    };
+    let duration = last - first;
+
+    let (rect, response) = ui.allocate_exact_size(
+        Vec2::new(ui.available_width(), STRIP_HEIGHT),
+        Sense::hover(),
+    );
+    let x_at = |time: f64| rect.left() + ((time - first) / duration) as f32 * rect.width();
+    let painter = ui.painter_at(rect);
+    for segment in fd.phases.segments.iter() {
+        let start = times[segment.start];
+        let end = times[segment.end.min(times.len()) - 1];
+        let segment_rect = Rect::from_x_y_ranges(x_at(start)..=x_at(end), rect.y_range());
+        let mut color = phase_color(&colors, segment.phase);
+        if !selected.contains(&segment.phase) {
+            color = color.gamma_multiply(0.25);
+        }
+        painter.rect_filled(segment_rect, 0.0, color);
+    }
+    // sensor clipping in the lower half, so the phase stays visible
+    let clipping_spans = fd
+        .sensor_clipping
+        .iter()
+        .flat_map(|c| c.gyro.iter().chain(c.accel.iter()))
+        .flat_map(|s| s.spans.iter());
+    for (start, end) in clipping_spans {
+        let x_range = x_at(*start)..=f32::max(x_at(*end), x_at(*start) + 1.0);
+        let clipping_rect = Rect::from_x_y_ranges(x_range, rect.center().y..=rect.bottom());
+        painter.rect_filled(clipping_rect, 0.0, colors.sensor_clipping);
+    }
+    painter.rect_stroke(rect, 0.0, Stroke::new(1.0, ui.visuals().weak_text_color()));
+    for crash in fd.crashes.iter() {
+        let stroke_width = if crash.is_likely() { 3.0 } else { 1.0 };
+        painter.vline(
+            x_at(crash.time),
+            rect.y_range(),
+            Stroke::new(stroke_width, colors.error),
+        );
+    }
+
+    if let Some(pos) = response.hover_pos() {
+        let time = first + ((pos.x - rect.left()) / rect.width()) as f64 * duration;
+        let hovered = fd
+            .phases
+            .segments
+            .iter()
+            .find(|s| times[s.start] <= time && time <= times[s.end.min(times.len()) - 1]);
+        let crash = fd.crashes.iter().find(|c| {
+            let x = x_at(c.time);
+            (x - pos.x).abs() <= 3.0
+        });
+        if let Some(crash) = crash {
+            response.on_hover_text(format!(
+                "Crash candidate at {:.1}s{}",
+                crash.time - first,
+                crash
+                    .peak_g
+                    .map(|g| format!(", {:.1}g", g))
+                    .unwrap_or_default()
+            ));
+        } else if let Some(segment) = hovered {
+            response.on_hover_text(format!(
+                "{} ({:.1}s – {:.1}s)",
+                segment.phase,
+                times[segment.start] - first,
+                times[segment.end.min(times.len()) - 1] - first
+            ));
+        }
+    }
+
+    let present: Vec<(FlightPhase, f64)> = FlightPhase::ALL
+        .into_iter()
+        .map(|phase| (phase, fd.phases.duration(times, phase)))
+        .filter(|(_, duration)| *duration > 0.0)
+        .collect();
+
+    let mut changed = false;
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Analyse phases:");
+        let selected_present = present
+            .iter()
+            .filter(|(phase, _)| selected.contains(phase))
+            .count();
+        for (phase, phase_duration) in present.iter().copied() {
+            let is_selected = selected.contains(&phase);
+            let text = egui::RichText::new(format!("{} {:.0}s", phase, phase_duration))
+                .color(phase_color(&colors, phase));
+            // keep at least one phase, an empty flight can't be analysed
+            if ui.selectable_label(is_selected, text).clicked()
+                && !(is_selected && selected_present == 1)
+            {
+                if is_selected {
+                    selected.retain(|p| *p != phase);
+                } else {
+                    selected.push(phase);
+                }
+                changed = true;
+            }
+        }
+
+        if selected.len() != FlightPhase::ALL.len() && ui.button("All").clicked() {
+            *selected = FlightPhase::ALL.to_vec();
+            changed = true;
+        }
+    });
+
+    changed
+}
@@ -1,15 +1,22 @@
+mod pilot;
 mod plot;
+mod timing;
 mod tune;
 mod vibe;
 
 use std::fmt::Display;
 
+pub use pilot::*;
 pub use plot::*;
+pub use timing::*;
 pub use tune::*;
 pub use vibe::*;
 
+/// Id of the `TimeseriesGroup` linking the timeseries plots of a flight in time
+pub const TIMESERIES_GROUP_ID: &str = "timeseries_plots";
 const PLOT_HEIGHT: f32 = 300.0;
 const MIN_WIDE_WIDTH: f32 = 1000.0;
+const AXIS_LABELS: [&str; 3] = ["Roll", "Pitch", "Yaw"];
 
 #[derive(Default, Clone, Copy, PartialEq)]
 pub enum FlightViewTab {
@@ -17,6 +24,8 @@ pub enum FlightViewTab {
     Plot,
     Tune,
     Vibe,
+    Pilot,
+    Timing,
 }
 
 impl Display for FlightViewTab {
@@ -25,6 +34,8 @@ impl Display for FlightViewTab {
             Self::Plot => "🗠  Plot",
             Self::Tune => "⛭  Tune",
             Self::Vibe => "💃 Vibe",
+            Self::Pilot => "🎮 Pilot",
+            Self::Timing => "⏱ Timing",
         };
         write!(f, "{val}",)
     }
@@ -0,0 +1,314 @@
+use std::ops::Range;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use egui::{Color32, RichText, TextureHandle};
+use egui_plot::{Bar, BarChart, Corner, Legend};
+
+use crate::flight_data::FlightData;
+use crate::gui::colors::Colors;
+use crate::gui::flex::FlexColumns;
+use crate::stick_usage::{
+    analyze_stick_usage, Heatmap, StickUsage, DEFLECTION_BIN_WIDTH, HEATMAP_BINS,
+    STICK_SPEED_BIN_WIDTH, THROTTLE_BIN_WIDTH,
+};
+use crate::utils::{execute_in_background, BackgroundCompStore};
+
+use super::{AXIS_LABELS, MIN_WIDE_WIDTH, PLOT_HEIGHT};
+
+#[derive(PartialEq, Clone, Copy)]
+enum HistogramView {
+    Deflection,
+    Speed,
+}
+
+struct PilotData {
+    usage: StickUsage,
+    left_texture: TextureHandle,
+    right_texture: TextureHandle,
+}
+
+fn heatmap_image(heatmap: &Heatmap) -> egui::ColorImage {
+    let gradient = colorgrad::inferno();
+    let mut image = egui::ColorImage::new([HEATMAP_BINS, HEATMAP_BINS], Color32::TRANSPARENT);
+
+    // Log scale, otherwise everything but the centered sticks disappears
+    let max = f32::ln_1p(heatmap.max);
+    for row in 0..HEATMAP_BINS {
+        for col in 0..HEATMAP_BINS {
+            let share = heatmap.cells[row * HEATMAP_BINS + col];
+            if share <= 0.0 || max <= 0.0 {
+                continue;
+            }
+
+            let rgba = gradient.at((share.ln_1p() / max) as f64).to_rgba8();
+            image[(col, HEATMAP_BINS - 1 - row)] = Color32::from_rgb(rgba[0], rgba[1], rgba[2]);
+        }
+    }
+
+    image
+}
+
+/// How the pilot used the sticks, to compare flying styles and choose rates.
+pub struct PilotTab {
+    ctx: egui::Context,
+    fd: Arc<FlightData>,
+    data: BackgroundCompStore<Option<PilotData>>,
+    histogram_view: HistogramView,
+}
+
+impl PilotTab {
+    pub fn new(ctx: &egui::Context, fd: Arc<FlightData>, ranges: Vec<Range<usize>>) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            data: Self::calculate(ctx, fd.clone(), ranges),
+            fd,
+            histogram_view: HistogramView::Deflection,
+        }
+    }
+
+    /// Recalculates the stick usage for other `ranges`, keeping the selected histogram.
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.data = Self::calculate(&self.ctx, self.fd.clone(), ranges);
+    }
+
+    fn calculate(
+        ctx: &egui::Context,
+        fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
+    ) -> BackgroundCompStore<Option<PilotData>> {
+        let (sender, receiver) = channel();
+        let ctx = ctx.clone();
+        execute_in_background(async move {
+            let data = fd.rc_command().map(|rc_command| {
+                let usage = analyze_stick_usage(
+                    &fd.times,
+                    rc_command.map(|v| v.as_slice()),
+                    fd.sample_rate(),
+                    &ranges,
+                );
+                let left_texture =
+                    ctx.load_texture("stick_left", heatmap_image(&usage.left), Default::default());
+                let right_texture = ctx.load_texture(
+                    "stick_right",
+                    heatmap_image(&usage.right),
+                    Default::default(),
+                );
+                PilotData {
+                    usage,
+                    left_texture,
+                    right_texture,
+                }
+            });
+            let _ = sender.send(data);
+            ctx.request_repaint();
+        });
+        BackgroundCompStore::new(receiver)
+    }
+
+    fn show_heatmap(
+        ui: &mut egui::Ui,
+        texture: &TextureHandle,
+        x_label: &'static str,
+        y_label: &'static str,
+    ) -> egui::Response {
+        egui_plot::Plot::new(ui.next_auto_id())
+            .show_grid(false)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .data_aspect(1.0)
+            .include_x(-100.0)
+            .include_x(100.0)
+            .include_y(-100.0)
+            .include_y(100.0)
+            .x_axis_label(x_label)
+            .y_axis_label(y_label)
+            .x_axis_formatter(|mark, _, _| format!("{}%", mark.value))
+            .y_axis_formatter(|mark, _, _| format!("{}%", mark.value))
+            .label_formatter(move |_name, val| {
+                format!("{}: {:.0}%\n{}: {:.0}%", x_label, val.x, y_label, val.y)
+            })
+            .width(PLOT_HEIGHT)
+            .height(PLOT_HEIGHT)
+            .show(ui, |plot_ui| {
+                plot_ui.image(egui_plot::PlotImage::new(
+                    texture,
+                    egui_plot::PlotPoint::new(0.0, 0.0),
+                    egui::Vec2::new(200.0, 200.0),
+                ));
+            })
+            .response
+    }
+
+    fn show_summary(ui: &mut egui::Ui, usage: &StickUsage) {
+        egui::Grid::new(ui.next_auto_id())
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Axis");
+                ui.strong("Mean deflection");
+                ui.strong("Full deflection")
+                    .on_hover_text("Share of time at 95% deflection or more");
+                ui.strong("Stick speed (p95)");
+                ui.end_row();
+
+                for (label, axis) in AXIS_LABELS.iter().zip(usage.axes.iter()) {
+                    ui.label(*label);
+                    ui.monospace(format!("{:.1}%", axis.mean_deflection));
+                    ui.monospace(format!("{:.1}%", axis.full_deflection));
+                    ui.monospace(format!("{:.0}%/s", axis.speed_p95));
+                    ui.end_row();
+                }
+
+                ui.label("Throttle");
+                ui.monospace(format!("{:.1}%", usage.mean_throttle));
+                ui.end_row();
+            });
+    }
+
+    fn show_histogram(
+        ui: &mut egui::Ui,
+        name: String,
+        color: Color32,
+        bars: Vec<Bar>,
+        unit: &'static str,
+        height: f32,
+    ) {
+        egui_plot::Plot::new(ui.next_auto_id())
+            .legend(Legend::default().position(Corner::RightTop))
+            .show_grid(true)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .y_axis_position(egui_plot::HPlacement::Right)
+            .y_axis_width(3)
+            .x_axis_formatter(move |mark, _, _| format!("{}{}", mark.value, unit))
+            .y_axis_formatter(|mark, _, _| format!("{}%", mark.value))
+            .height(height)
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(BarChart::new(bars).name(name).color(color));
+            });
+    }
+
+    fn show_throttle_time(ui: &mut egui::Ui, usage: &StickUsage, color: Color32) {
+        let bars = usage
+            .throttle_time
+            .iter()
+            .enumerate()
+            .map(|(bin, time)| {
+                let center = (bin as f32 + 0.5) * THROTTLE_BIN_WIDTH;
+                Bar::new(center as f64, *time).width(THROTTLE_BIN_WIDTH as f64)
+            })
+            .collect();
+
+        egui_plot::Plot::new(ui.next_auto_id())
+            .legend(Legend::default().position(Corner::RightTop))
+            .show_grid(true)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_x(0.0)
+            .include_x(100.0)
+            .include_y(0.0)
+            .y_axis_position(egui_plot::HPlacement::Right)
+            .y_axis_width(3)
+            .x_axis_formatter(|mark, _, _| format!("{}%", mark.value))
+            .y_axis_formatter(|mark, _, _| format!("{}s", mark.value))
+            .height(PLOT_HEIGHT)
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(BarChart::new(bars).name("Time at throttle").color(color));
+            });
+    }
+
+    fn show_histograms(
+        ui: &mut egui::Ui,
+        histogram_view: &mut HistogramView,
+        usage: &StickUsage,
+        total_width: f32,
+    ) {
+        let colors = Colors::get(ui);
+
+        ui.horizontal_wrapped(|ui| {
+            for (view, label) in [
+                (HistogramView::Deflection, "Deflection"),
+                (HistogramView::Speed, "Stick Speed"),
+            ] {
+                ui.selectable_value(histogram_view, view, RichText::new(label).heading());
+            }
+        });
+
+        for (i, axis) in usage.axes.iter().enumerate() {
+            let height = if ui.available_width() < total_width {
+                ui.available_height() / (3 - i) as f32
+            } else {
+                PLOT_HEIGHT
+            };
+
+            let (values, offset, width, unit) = match histogram_view {
+                HistogramView::Deflection => (&axis.deflection, -100.0, DEFLECTION_BIN_WIDTH, "%"),
+                HistogramView::Speed => (&axis.speed, 0.0, STICK_SPEED_BIN_WIDTH, "%/s"),
+            };
+            let bars = values
+                .iter()
+                .enumerate()
+                .map(|(bin, share)| {
+                    let center = offset + (bin as f32 + 0.5) * width;
+                    Bar::new(center as f64, *share as f64).width(width as f64)
+                })
+                .collect();
+
+            Self::show_histogram(
+                ui,
+                AXIS_LABELS[i].to_string(),
+                colors.triple_primary[i],
+                bars,
+                unit,
+                height,
+            );
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let Self {
+            data,
+            histogram_view,
+            ..
+        } = self;
+        let Some(data) = data.get() else {
+            ui.spinner();
+            return;
+        };
+        let Some(data) = data.as_ref() else {
+            ui.label("This log has no RC commands.");
+            return;
+        };
+
+        let total_width = ui.available_width();
+        let throttle_color = Colors::get(ui).quad[3];
+
+        FlexColumns::new(MIN_WIDE_WIDTH)
+            .column(|ui| {
+                ui.vertical(|ui| {
+                    ui.heading("Sticks");
+                    ui.horizontal(|ui| {
+                        Self::show_heatmap(ui, &data.left_texture, "Yaw", "Throttle");
+                        Self::show_heatmap(ui, &data.right_texture, "Roll", "Pitch");
+                    });
+                    Self::show_summary(ui, &data.usage);
+
+                    ui.heading("Throttle");
+                    Self::show_throttle_time(ui, &data.usage, throttle_color);
+                })
+                .response
+            })
+            .column(|ui| {
+                ui.vertical(|ui| {
+                    Self::show_histograms(ui, histogram_view, &data.usage, total_width);
+                })
+                .response
+            })
+            .show(ui);
+    }
+}
@@ -1,13 +1,29 @@
+use std::sync::mpsc::channel;
 use std::sync::Arc;
 
+use egui::RichText;
 use egui_oszi::{TimeseriesGroup, TimeseriesLine, TimeseriesPlot, TimeseriesPlotMemory};
-use egui_plot::{Corner, Legend};
+use egui_plot::{Bar, BarChart, Corner, Legend};
 
 use crate::flight_data::FlightData;
+use crate::flight_phases::FlightPhase;
 use crate::gui::colors::Colors;
+use crate::motor_health::{analyze_motor_health, MotorHealthAnalysis};
+use crate::saturation::{detect_saturation, SaturationAnalysis};
+use crate::utils::{execute_in_background, BackgroundCompStore};
 
 use super::PLOT_HEIGHT;
 
+mod dashboard;
+
+use dashboard::Dashboard;
+
+#[derive(PartialEq, Clone, Copy)]
+enum PlotView {
+    Overview,
+    Dashboard,
+}
+
 pub struct PlotTab {
     gyro_plot: TimeseriesPlotMemory<f64, f32>,
     acc_plot: TimeseriesPlotMemory<f64, f32>,
@@ -17,10 +33,40 @@ pub struct PlotTab {
     motor_plot: TimeseriesPlotMemory<f64, f32>,
     erpm_plot: TimeseriesPlotMemory<f64, f32>,
     fd: Arc<FlightData>,
+    saturation: BackgroundCompStore<SaturationAnalysis>,
+    motor_health: BackgroundCompStore<MotorHealthAnalysis>,
+    dashboard: Dashboard,
+    view: PlotView,
 }
 
 impl PlotTab {
     pub fn new(fd: Arc<FlightData>) -> Self {
+        let (saturation_sender, receiver) = channel();
+        let saturation = BackgroundCompStore::new(receiver);
+        let (health_sender, receiver) = channel();
+        let motor_health = BackgroundCompStore::new(receiver);
+        let fd_clone = fd.clone();
+        execute_in_background(async move {
+            let motors = fd_clone.motor().unwrap_or_default();
+            let output_range = fd_clone.motor_output_range();
+            let in_flight: Vec<FlightPhase> = FlightPhase::ALL
+                .into_iter()
+                .filter(|p| *p != FlightPhase::Idle)
+                .collect();
+            let saturation = detect_saturation(
+                &fd_clone.times,
+                &motors,
+                output_range,
+                &fd_clone.phases.mask(&in_flight),
+            );
+            let _ = saturation_sender.send(saturation);
+
+            let erpm = fd_clone.electrical_rpm();
+            let health =
+                analyze_motor_health(&fd_clone.times, &motors, erpm.as_deref(), output_range);
+            let _ = health_sender.send(health);
+        });
+
         Self {
             gyro_plot: TimeseriesPlotMemory::new("gyro"),
             acc_plot: TimeseriesPlotMemory::new("acc"),
@@ -29,15 +75,161 @@ impl PlotTab {
             rssi_plot: TimeseriesPlotMemory::new("rssi"),
             motor_plot: TimeseriesPlotMemory::new("motors"),
             erpm_plot: TimeseriesPlotMemory::new("erpm"),
+            dashboard: Dashboard::new(fd.clone()),
+            view: PlotView::Overview,
             fd,
+            saturation,
+            motor_health,
         }
     }
 
+    /// Swaps in a new version of the flight, e.g. with derived series added. Only the dashboard
+    /// shows those, the analyses and plots of the logged fields are kept.
+    pub fn set_flight_data(&mut self, fd: Arc<FlightData>) {
+        self.dashboard.set_flight_data(fd.clone());
+        self.fd = fd;
+    }
+
+    fn show_motor_health_report(ui: &mut egui::Ui, health: &MotorHealthAnalysis, colors: &Colors) {
+        egui::CollapsingHeader::new("Motor Health").show(ui, |ui| {
+            for warning in health.warnings() {
+                ui.colored_label(colors.error, format!("⚠ {}", warning));
+            }
+
+            let charts: Vec<_> = health
+                .motors
+                .iter()
+                .enumerate()
+                .map(|(i, motor)| {
+                    let bar = Bar::new(i as f64, motor.mean_output as f64).width(0.6);
+                    BarChart::new(vec![bar])
+                        .name(format!("motor[{}]", i))
+                        .color(colors.motors[i % colors.motors.len()])
+                })
+                .collect();
+            egui_plot::Plot::new(ui.next_auto_id())
+                .show_grid(true)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .y_axis_position(egui_plot::HPlacement::Right)
+                .y_axis_width(3)
+                .y_axis_formatter(|mark, _, _| format!("{}%", mark.value))
+                .x_axis_formatter(|mark, _, _| {
+                    if mark.value.fract() == 0.0 && mark.value >= 0.0 {
+                        format!("motor[{}]", mark.value)
+                    } else {
+                        String::new()
+                    }
+                })
+                .height(PLOT_HEIGHT / 2.0)
+                .show(ui, |plot_ui| {
+                    for chart in charts {
+                        plot_ui.bar_chart(chart);
+                    }
+                });
+
+            egui::Grid::new(ui.next_auto_id())
+                .num_columns(5)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Motor");
+                    ui.strong("Mean output");
+                    ui.strong("Std. dev.");
+                    ui.strong("eRPM vs. others").on_hover_text(
+                        "Deviation of the eRPM from the other motors at equal output",
+                    );
+                    ui.strong("Hardest working").on_hover_text(
+                        "Share of the flight in which this motor had the highest output",
+                    );
+                    ui.end_row();
+
+                    for (i, motor) in health.motors.iter().enumerate() {
+                        ui.label(format!("motor[{}]", i));
+                        ui.monospace(format!("{:.1}%", motor.mean_output));
+                        ui.monospace(format!("{:.1}%", motor.output_std));
+                        ui.monospace(
+                            motor
+                                .rpm_deviation
+                                .map(|d| format!("{:+.1}%", d))
+                                .unwrap_or_default(),
+                        );
+                        ui.monospace(format!("{:.0}%", motor.hardest_working));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn show_saturation_report(ui: &mut egui::Ui, saturation: &SaturationAnalysis) {
+        let percentage = |time: f64| {
+            if saturation.duration > 0.0 {
+                100.0 * time / saturation.duration
+            } else {
+                0.0
+            }
+        };
+
+        egui::CollapsingHeader::new("Motor Saturation").show(ui, |ui| {
+            egui::Grid::new(ui.next_auto_id())
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Motor");
+                    ui.strong("At min");
+                    ui.strong("At max");
+                    ui.end_row();
+
+                    for (i, motor) in saturation.motors.iter().enumerate() {
+                        ui.label(format!("motor[{}]", i));
+                        ui.label(format!(
+                            "{:.2}s ({:.1}%)",
+                            motor.at_min,
+                            percentage(motor.at_min)
+                        ));
+                        ui.label(format!(
+                            "{:.2}s ({:.1}%)",
+                            motor.at_max,
+                            percentage(motor.at_max)
+                        ));
+                        ui.end_row();
+                    }
+
+                    ui.label("Mixer clipping")
+                        .on_hover_text("One motor at max while another is at min");
+                    ui.label(format!(
+                        "{:.2}s ({:.1}%)",
+                        saturation.clipping,
+                        percentage(saturation.clipping)
+                    ));
+                    ui.end_row();
+                });
+        });
+    }
+
     pub fn show(&mut self, ui: &mut egui::Ui, timeseries_group: &mut TimeseriesGroup) {
+        ui.horizontal_wrapped(|ui| {
+            let view = &mut self.view;
+            for (value, label) in [
+                (PlotView::Overview, "Overview"),
+                (PlotView::Dashboard, "Dashboard"),
+            ] {
+                ui.selectable_value(view, value, RichText::new(label).heading());
+            }
+        });
+
+        match self.view {
+            PlotView::Overview => self.show_overview(ui, timeseries_group),
+            PlotView::Dashboard => self.dashboard.show(ui, timeseries_group),
+        }
+    }
+
+    fn show_overview(&mut self, ui: &mut egui::Ui, timeseries_group: &mut TimeseriesGroup) {
         let times = &self.fd.times;
         let legend = Legend::default().position(Corner::LeftTop);
 
         let colors = Colors::get(ui);
+        let sensor_clipping = self.fd.sensor_clipping.as_ref();
 
         ui.heading("Gyroscope");
         ui.add(
@@ -98,6 +290,15 @@ impl PlotTab {
                             .map(|s| s[2].iter().copied())
                             .unwrap_or_default(),
                     ),
+                )
+                .line(
+                    TimeseriesLine::new("Gyro clipping").color(colors.sensor_clipping),
+                    times.iter().copied().zip(
+                        sensor_clipping
+                            .and_then(|c| c.gyro.as_ref())
+                            .map(|c| c.indicator.iter().copied())
+                            .unwrap_or_default(),
+                    ),
                 ),
         );
 
@@ -133,6 +334,15 @@ impl PlotTab {
                             .map(|s| s[2].iter().copied())
                             .unwrap_or_default(),
                     ),
+                )
+                .line(
+                    TimeseriesLine::new("Acc clipping").color(colors.sensor_clipping),
+                    times.iter().copied().zip(
+                        sensor_clipping
+                            .and_then(|c| c.accel.as_ref())
+                            .map(|c| c.indicator.iter().copied())
+                            .unwrap_or_default(),
+                    ),
                 ),
         );
 
@@ -180,6 +390,8 @@ impl PlotTab {
                 ),
         );
 
+        let saturation = self.saturation.get().as_ref();
+
         ui.heading("Motors");
         ui.add(
             TimeseriesPlot::new(&mut self.motor_plot)
@@ -221,8 +433,30 @@ impl PlotTab {
                             .map(|s| s[3].iter().copied())
                             .unwrap_or_default(),
                     ),
+                )
+                .line(
+                    TimeseriesLine::new("Saturation").color(colors.saturation),
+                    times.iter().copied().zip(
+                        saturation
+                            .map(|s| s.saturation_indicator.iter().copied())
+                            .unwrap_or_default(),
+                    ),
+                )
+                .line(
+                    TimeseriesLine::new("Mixer clipping").color(colors.clipping),
+                    times.iter().copied().zip(
+                        saturation
+                            .map(|s| s.clipping_indicator.iter().copied())
+                            .unwrap_or_default(),
+                    ),
                 ),
         );
+        if let Some(saturation) = saturation {
+            Self::show_saturation_report(ui, saturation);
+        }
+        if let Some(health) = self.motor_health.get() {
+            Self::show_motor_health_report(ui, health, &colors);
+        }
 
         ui.heading("eRPM");
         ui.add(
@@ -291,6 +525,26 @@ impl PlotTab {
                             .map(|s| s.iter().copied())
                             .unwrap_or_default(),
                     ),
+                )
+                .line(
+                    TimeseriesLine::new("Power (W)").color(colors.power),
+                    times.iter().copied().zip(
+                        self.fd
+                            .battery
+                            .as_ref()
+                            .map(|b| b.power.iter().copied())
+                            .unwrap_or_default(),
+                    ),
+                )
+                .line(
+                    TimeseriesLine::new("Consumed (mAh)").color(colors.consumed),
+                    times.iter().copied().zip(
+                        self.fd
+                            .battery
+                            .as_ref()
+                            .map(|b| b.consumed.iter().copied())
+                            .unwrap_or_default(),
+                    ),
                 ),
         );
 
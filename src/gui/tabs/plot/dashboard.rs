@@ -0,0 +1,760 @@
+use std::collections::HashMap;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use egui::{Color32, RichText};
+use egui_oszi::{TimeseriesGroup, TimeseriesLine, TimeseriesPlot, TimeseriesPlotMemory};
+use egui_plot::{AxisHints, Corner, HPlacement, Legend, PlotMemory, PlotPoints};
+
+use crate::flight_data::FlightData;
+use crate::gui::colors::Colors;
+use crate::gui::export::csv_export_buttons;
+use crate::utils::{execute_in_background, BackgroundCompStore};
+
+use super::super::{PLOT_HEIGHT, TIMESERIES_GROUP_ID};
+
+#[cfg(not(target_arch = "wasm32"))]
+const PRESETS_FILE_NAME: &str = "dashboard_presets.txt";
+const PRESETS_HEADER: &str = "# bucksaw dashboard presets";
+/// Upper limit for the number of points drawn per line in plots with a second axis
+const MAX_LINE_POINTS: usize = 4000;
+
+/// Presets are shared by all flights of the session. Native builds also keep them in a file in
+/// the config directory, see `presets_path`.
+fn presets_id() -> egui::Id {
+    egui::Id::new("dashboard_presets")
+}
+
+/// `bucksaw/dashboard_presets.txt` in the user's config directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn presets_path() -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("bucksaw").join(PRESETS_FILE_NAME))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_presets() -> Vec<DashboardPreset> {
+    let Some(path) = presets_path() else {
+        return Vec::new();
+    };
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    presets_from_text(&text).unwrap_or_else(|e| {
+        log::error!("Failed to read {:?}: {}", path, e);
+        Vec::new()
+    })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_presets() -> Vec<DashboardPreset> {
+    Vec::new()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn store_presets(presets: &[DashboardPreset]) {
+    let Some(path) = presets_path() else {
+        return;
+    };
+    let result = path
+        .parent()
+        .map(std::fs::create_dir_all)
+        .unwrap_or(Ok(()))
+        .and_then(|_| std::fs::write(&path, presets_to_text(presets)));
+    if let Err(e) = result {
+        log::error!("Failed to write {:?}: {}", path, e);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn store_presets(_presets: &[DashboardPreset]) {}
+
+#[derive(Clone, PartialEq)]
+struct DashboardField {
+    /// Key into `FlightData::main_values`
+    name: String,
+    color: Color32,
+    /// Shown against a second axis on the right of the plot
+    secondary: bool,
+}
+
+#[derive(Clone, PartialEq)]
+struct DashboardPlot {
+    title: String,
+    fields: Vec<DashboardField>,
+}
+
+#[derive(Clone, PartialEq)]
+struct DashboardPreset {
+    name: String,
+    plots: Vec<DashboardPlot>,
+}
+
+fn format_color(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn parse_color(text: &str) -> Option<Color32> {
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Line based text format, field names go last since they may contain spaces:
+///
+/// ```text
+/// preset <name>
+/// plot <title>
+/// field <#rrggbb> <left|right> <name>
+/// ```
+fn presets_to_text(presets: &[DashboardPreset]) -> String {
+    let mut text = format!("{}\n", PRESETS_HEADER);
+    for preset in presets.iter() {
+        text += &format!("preset {}\n", preset.name);
+        for plot in preset.plots.iter() {
+            text += &format!("plot {}\n", plot.title);
+            for field in plot.fields.iter() {
+                let axis = if field.secondary { "right" } else { "left" };
+                text += &format!(
+                    "field {} {} {}\n",
+                    format_color(field.color),
+                    axis,
+                    field.name
+                );
+            }
+        }
+    }
+    text
+}
+
+fn presets_from_text(text: &str) -> Result<Vec<DashboardPreset>, String> {
+    let mut presets: Vec<DashboardPreset> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: &str| format!("Line {}: {}", i + 1, message);
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        match keyword {
+            "preset" => presets.push(DashboardPreset {
+                name: rest.to_string(),
+                plots: Vec::new(),
+            }),
+            "plot" => presets
+                .last_mut()
+                .ok_or_else(|| error("plot outside of a preset"))?
+                .plots
+                .push(DashboardPlot {
+                    title: rest.to_string(),
+                    fields: Vec::new(),
+                }),
+            "field" => {
+                let mut parts = rest.splitn(3, ' ');
+                let color = parts
+                    .next()
+                    .and_then(parse_color)
+                    .ok_or_else(|| error("invalid field colour"))?;
+                let secondary = match parts.next() {
+                    Some("left") => false,
+                    Some("right") => true,
+                    _ => return Err(error("field axis must be left or right")),
+                };
+                let name = parts
+                    .next()
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| error("missing field name"))?;
+                presets
+                    .last_mut()
+                    .and_then(|preset| preset.plots.last_mut())
+                    .ok_or_else(|| error("field outside of a plot"))?
+                    .fields
+                    .push(DashboardField {
+                        name: name.to_string(),
+                        color,
+                        secondary,
+                    });
+            }
+            _ => return Err(error(&format!("unknown keyword {:?}", keyword))),
+        }
+    }
+    Ok(presets)
+}
+
+/// Adds `imported` to `presets`, replacing presets with the same name.
+fn merge_presets(presets: &mut Vec<DashboardPreset>, imported: Vec<DashboardPreset>) {
+    for preset in imported {
+        match presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
+}
+
+/// Range of the finite values of a field, cached since it is needed every frame.
+fn field_range(
+    ranges: &mut HashMap<String, Option<(f32, f32)>>,
+    fd: &FlightData,
+    name: &str,
+) -> Option<(f32, f32)> {
+    *ranges.entry(name.to_string()).or_insert_with(|| {
+        fd.main_values.get(name).and_then(|values| {
+            values
+                .iter()
+                .copied()
+                .filter(|v| v.is_finite())
+                .fold(None, |range, v| match range {
+                    None => Some((v, v)),
+                    Some((min, max)) => Some((f32::min(min, v), f32::max(max, v))),
+                })
+        })
+    })
+}
+
+fn union_range(ranges: impl Iterator<Item = (f32, f32)>) -> Option<(f32, f32)> {
+    ranges.reduce(|a, b| (f32::min(a.0, b.0), f32::max(a.1, b.1)))
+}
+
+/// Linear mapping of the secondary fields onto the range of the primary ones, which the plot
+/// draws them in. The right axis maps the values back.
+#[derive(Clone, Copy)]
+struct SecondAxis {
+    primary: (f64, f64),
+    secondary: (f64, f64),
+}
+
+impl SecondAxis {
+    fn new(primary: Option<(f32, f32)>, secondary: Option<(f32, f32)>) -> Option<Self> {
+        let ((p_min, p_max), (s_min, s_max)) = primary.zip(secondary)?;
+        (p_max > p_min && s_max > s_min).then_some(Self {
+            primary: (p_min as f64, p_max as f64),
+            secondary: (s_min as f64, s_max as f64),
+        })
+    }
+
+    fn to_primary(self, value: f64) -> f64 {
+        let ((p_min, p_max), (s_min, s_max)) = (self.primary, self.secondary);
+        p_min + (value - s_min) / (s_max - s_min) * (p_max - p_min)
+    }
+
+    fn to_secondary(self, value: f64) -> f64 {
+        let ((p_min, p_max), (s_min, s_max)) = (self.primary, self.secondary);
+        s_min + (value - p_min) / (p_max - p_min) * (s_max - s_min)
+    }
+
+    /// Decimals for labelling the secondary values, more for small ranges
+    fn decimals(&self) -> usize {
+        let span = self.secondary.1 - self.secondary.0;
+        if span >= 100.0 {
+            0
+        } else if span >= 10.0 {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+/// The samples within `x_range` (all of them if `None`), reduced to the minimum and maximum of
+/// each bucket so that spikes survive.
+fn decimated_points(
+    times: &[f64],
+    values: &[f32],
+    x_range: Option<(f64, f64)>,
+    map: impl Fn(f64) -> f64,
+) -> Vec<[f64; 2]> {
+    let len = times.len().min(values.len());
+    let (start, end) = match x_range {
+        Some((min, max)) => (
+            times[..len].partition_point(|t| *t < min).saturating_sub(1),
+            (times[..len].partition_point(|t| *t <= max) + 1).min(len),
+        ),
+        None => (0, len),
+    };
+    if start >= end {
+        return Vec::new();
+    }
+
+    let bucket = (end - start).div_ceil(MAX_LINE_POINTS / 2).max(1);
+    let mut points = Vec::with_capacity(2 * (end - start) / bucket + 2);
+    for chunk_start in (start..end).step_by(bucket) {
+        let indices = chunk_start..usize::min(chunk_start + bucket, end);
+        let by_value = |a: &usize, b: &usize| values[*a].total_cmp(&values[*b]);
+        let (Some(min), Some(max)) = (
+            indices.clone().min_by(by_value),
+            indices.clone().max_by(by_value),
+        ) else {
+            continue;
+        };
+        for i in [min.min(max), min.max(max)] {
+            points.push([times[i], map(values[i] as f64)]);
+        }
+    }
+    points.dedup();
+    points
+}
+
+enum PlotAction {
+    MoveUp(usize),
+    MoveDown(usize),
+    Remove(usize),
+}
+
+/// User-editable list of plots of arbitrary fields.
+pub struct Dashboard {
+    fd: Arc<FlightData>,
+    /// All fields of the flight, sorted by name
+    field_names: Vec<String>,
+    /// The plots with their memory and the id of the plot drawn when they have a second axis
+    plots: Vec<(DashboardPlot, TimeseriesPlotMemory<f64, f32>, egui::Id)>,
+    next_plot_id: usize,
+    editing: bool,
+    preset_name: String,
+    ranges: HashMap<String, Option<(f32, f32)>>,
+    import: Option<BackgroundCompStore<Result<Vec<DashboardPreset>, String>>>,
+    import_error: Option<String>,
+}
+
+impl Dashboard {
+    pub fn new(fd: Arc<FlightData>) -> Self {
+        let mut dashboard = Self {
+            field_names: Self::field_names(&fd),
+            fd,
+            plots: Vec::new(),
+            next_plot_id: 0,
+            editing: true,
+            preset_name: String::new(),
+            ranges: HashMap::new(),
+            import: None,
+            import_error: None,
+        };
+        dashboard.add_plot(DashboardPlot {
+            title: "Plot 1".to_string(),
+            fields: Vec::new(),
+        });
+        dashboard
+    }
+
+    fn field_names(fd: &FlightData) -> Vec<String> {
+        let mut field_names: Vec<String> = fd.main_values.keys().cloned().collect();
+        field_names.sort();
+        field_names
+    }
+
+    /// Keeps the plots for a new version of the flight, e.g. with derived series added.
+    pub fn set_flight_data(&mut self, fd: Arc<FlightData>) {
+        self.field_names = Self::field_names(&fd);
+        self.ranges.clear();
+        self.fd = fd;
+    }
+
+    fn add_plot(&mut self, plot: DashboardPlot) {
+        let memory = TimeseriesPlotMemory::new(format!("dashboard_{}", self.next_plot_id));
+        let id = egui::Id::new(("dashboard_second_axis", self.next_plot_id));
+        self.next_plot_id += 1;
+        self.plots.push((plot, memory, id));
+    }
+
+    fn load_preset(&mut self, preset: &DashboardPreset) {
+        self.plots.clear();
+        for plot in preset.plots.iter() {
+            self.add_plot(plot.clone());
+        }
+        self.preset_name = preset.name.clone();
+    }
+
+    fn current_preset(&self) -> DashboardPreset {
+        DashboardPreset {
+            name: self.preset_name.trim().to_string(),
+            plots: self.plots.iter().map(|(plot, ..)| plot.clone()).collect(),
+        }
+    }
+
+    /// Time and all fields of the plots, for analysing them elsewhere.
+    fn to_csv(&self) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        for field in self.plots.iter().flat_map(|(plot, ..)| plot.fields.iter()) {
+            if !names.contains(&field.name.as_str()) {
+                names.push(&field.name);
+            }
+        }
+        let columns: Vec<_> = names
+            .iter()
+            .map(|name| self.fd.main_values.get(*name))
+            .collect();
+
+        let mut csv = String::from("time");
+        for name in names.iter() {
+            csv += &format!(",\"{}\"", name.replace('"', "\"\""));
+        }
+        csv += "\n";
+
+        for (i, time) in self.fd.times.iter().enumerate() {
+            csv += &format!("{}", time);
+            for column in columns.iter() {
+                csv += ",";
+                if let Some(value) = column.and_then(|c| c.get(i)) {
+                    csv += &format!("{}", value);
+                }
+            }
+            csv += "\n";
+        }
+        csv
+    }
+
+    fn import_presets(&mut self) {
+        let (sender, receiver) = channel();
+        execute_in_background(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("Dashboard presets", &["txt"])
+                .pick_file()
+                .await
+            else {
+                let _ = sender.send(Ok(Vec::new()));
+                return;
+            };
+
+            let bytes = file.read().await;
+            let presets = String::from_utf8(bytes)
+                .map_err(|e| e.to_string())
+                .and_then(|text| presets_from_text(&text));
+            let _ = sender.send(presets);
+        });
+        self.import = Some(BackgroundCompStore::new(receiver));
+        self.import_error = None;
+    }
+
+    fn show_presets(&mut self, ui: &mut egui::Ui) {
+        let ctx = ui.ctx().clone();
+        let mut presets: Vec<DashboardPreset> = match ctx.data(|d| d.get_temp(presets_id())) {
+            Some(presets) => presets,
+            None => {
+                let presets = load_presets();
+                ctx.data_mut(|d| d.insert_temp(presets_id(), presets.clone()));
+                presets
+            }
+        };
+        let mut changed = false;
+
+        if let Some(import) = self.import.as_mut() {
+            if let Some(result) = import.get() {
+                match result {
+                    Ok(imported) => {
+                        merge_presets(&mut presets, imported.clone());
+                        changed = true;
+                    }
+                    Err(e) => self.import_error = Some(e.clone()),
+                }
+                self.import = None;
+            }
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.toggle_value(&mut self.editing, "✏ Edit");
+
+            egui::ComboBox::from_id_source("dashboard_preset")
+                .selected_text("Load preset")
+                .show_ui(ui, |ui| {
+                    for preset in presets.iter() {
+                        if ui.selectable_label(false, &preset.name).clicked() {
+                            self.load_preset(preset);
+                        }
+                    }
+                });
+
+            ui.add(
+                egui::TextEdit::singleline(&mut self.preset_name)
+                    .hint_text("Preset name")
+                    .desired_width(120.0),
+            );
+            let name = self.preset_name.trim().to_string();
+            let exists = presets.iter().any(|p| p.name == name);
+            let save_label = if exists {
+                "💾 Update preset"
+            } else {
+                "💾 Save preset"
+            };
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new(save_label))
+                .clicked()
+            {
+                merge_presets(&mut presets, vec![self.current_preset()]);
+                changed = true;
+            }
+            if ui
+                .add_enabled(exists, egui::Button::new("🗑 Delete preset"))
+                .clicked()
+            {
+                presets.retain(|p| p.name != name);
+                changed = true;
+            }
+
+            ui.separator();
+
+            if ui
+                .add_enabled(!presets.is_empty(), egui::Button::new("📋 Copy presets"))
+                .clicked()
+            {
+                ui.output_mut(|o| o.copied_text = presets_to_text(&presets));
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .add_enabled(!presets.is_empty(), egui::Button::new("💾 Export presets"))
+                .clicked()
+            {
+                crate::utils::save_file(PRESETS_FILE_NAME.to_string(), presets_to_text(&presets));
+            }
+
+            if self.import.is_some() {
+                ui.spinner();
+            } else if ui.button("📂 Import presets").clicked() {
+                self.import_presets();
+            }
+
+            ui.separator();
+
+            let file_name = format!("dashboard_{}.csv", self.fd.index + 1);
+            csv_export_buttons(ui, &file_name, || self.to_csv());
+        });
+
+        if let Some(error) = &self.import_error {
+            let colors = Colors::get(ui);
+            ui.colored_label(colors.error, format!("⚠ Import failed: {}", error));
+        }
+
+        if changed {
+            store_presets(&presets);
+            ctx.data_mut(|d| d.insert_temp(presets_id(), presets));
+        }
+    }
+
+    fn show_plot_editor(
+        ui: &mut egui::Ui,
+        index: usize,
+        plot: &mut DashboardPlot,
+        field_names: &[String],
+        fd: &FlightData,
+        colors: &Colors,
+    ) -> Option<PlotAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut plot.title).desired_width(200.0));
+            if ui.small_button("⬆").clicked() {
+                action = Some(PlotAction::MoveUp(index));
+            }
+            if ui.small_button("⬇").clicked() {
+                action = Some(PlotAction::MoveDown(index));
+            }
+            if ui.small_button("🗑").on_hover_text("Remove plot").clicked() {
+                action = Some(PlotAction::Remove(index));
+            }
+        });
+
+        let mut removed = None;
+        for (i, field) in plot.fields.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgba(&mut field.color);
+                ui.monospace(&field.name);
+                if !fd.main_values.contains_key(&field.name) {
+                    ui.weak("(not in this log)");
+                }
+                ui.checkbox(&mut field.secondary, "2nd axis")
+                    .on_hover_text("Show against a second axis on the right");
+                if ui.small_button("✖").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            plot.fields.remove(i);
+        }
+
+        egui::ComboBox::from_id_source(("dashboard_add_field", index))
+            .selected_text("➕ Add field")
+            .height(400.0)
+            .show_ui(ui, |ui| {
+                for name in field_names.iter() {
+                    if plot.fields.iter().any(|f| &f.name == name) {
+                        continue;
+                    }
+                    if ui.selectable_label(false, name).clicked() {
+                        plot.fields.push(DashboardField {
+                            name: name.clone(),
+                            color: colors.motors[plot.fields.len() % colors.motors.len()],
+                            secondary: false,
+                        });
+                    }
+                }
+            });
+
+        action
+    }
+
+    fn second_axis_name(name: &str) -> String {
+        format!("{} (right)", name)
+    }
+
+    /// Draws a plot whose secondary fields get their own axis on the right. `TimeseriesPlot` only
+    /// has one axis, so these are plain plots, joined to the plot link group of the
+    /// `TimeseriesGroup` so they still move in time and share the cursor with all other plots.
+    fn show_second_axis_plot(
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        plot: &DashboardPlot,
+        fd: &FlightData,
+        axis: SecondAxis,
+        legend: Legend,
+    ) {
+        let decimals = axis.decimals();
+        let secondary_names: Vec<String> = plot
+            .fields
+            .iter()
+            .filter(|f| f.secondary)
+            .map(|f| Self::second_axis_name(&f.name))
+            .collect();
+        // only the part of the flight visible in the last frame is drawn in full detail
+        let x_range = PlotMemory::load(ui.ctx(), id).map(|memory| {
+            let bounds = memory.bounds();
+            (bounds.min()[0], bounds.max()[0])
+        });
+
+        egui_plot::Plot::new(id)
+            .id(id)
+            .legend(legend)
+            .height(PLOT_HEIGHT)
+            .link_axis(TIMESERIES_GROUP_ID, true, false)
+            .link_cursor(TIMESERIES_GROUP_ID, true, false)
+            .x_axis_formatter(|mark, _, _| format!("{}s", mark.value))
+            .custom_y_axes(vec![
+                AxisHints::new_y(),
+                AxisHints::new_y()
+                    .placement(HPlacement::Right)
+                    .formatter(move |mark, _, _| {
+                        format!("{:.*}", decimals, axis.to_secondary(mark.value))
+                    }),
+            ])
+            .label_formatter(move |name, point| {
+                if name.is_empty() {
+                    return format!("{:.3}s", point.x);
+                }
+                let value = if secondary_names.iter().any(|n| n == name) {
+                    axis.to_secondary(point.y)
+                } else {
+                    point.y
+                };
+                format!("{}\n{:.3}s\n{:.2}", name, point.x, value)
+            })
+            .show(ui, |plot_ui| {
+                for field in plot.fields.iter() {
+                    let Some(values) = fd.main_values.get(&field.name) else {
+                        continue;
+                    };
+                    let (name, points) = if field.secondary {
+                        let points =
+                            decimated_points(&fd.times, values, x_range, |v| axis.to_primary(v));
+                        (Self::second_axis_name(&field.name), points)
+                    } else {
+                        let points = decimated_points(&fd.times, values, x_range, |v| v);
+                        (field.name.clone(), points)
+                    };
+                    plot_ui.line(
+                        egui_plot::Line::new(PlotPoints::new(points))
+                            .name(name)
+                            .color(field.color),
+                    );
+                }
+            });
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, timeseries_group: &mut TimeseriesGroup) {
+        self.show_presets(ui);
+        ui.separator();
+
+        let colors = Colors::get(ui);
+        let fd = &self.fd;
+        let times = &fd.times;
+        let ranges = &mut self.ranges;
+        let legend = Legend::default().position(Corner::LeftTop);
+
+        let mut action = None;
+        for (index, (plot, memory, id)) in self.plots.iter_mut().enumerate() {
+            if self.editing {
+                ui.add_space(8.0);
+                action = action.or(Self::show_plot_editor(
+                    ui,
+                    index,
+                    plot,
+                    &self.field_names,
+                    fd,
+                    &colors,
+                ));
+            } else {
+                ui.heading(&plot.title);
+            }
+
+            let mut plot_range = |secondary: bool| {
+                let ranges: Vec<_> = plot
+                    .fields
+                    .iter()
+                    .filter(|f| f.secondary == secondary)
+                    .filter_map(|f| field_range(ranges, fd, &f.name))
+                    .collect();
+                union_range(ranges.into_iter())
+            };
+            if let Some(axis) = SecondAxis::new(plot_range(false), plot_range(true)) {
+                Self::show_second_axis_plot(ui, *id, plot, fd, axis, legend.clone());
+                continue;
+            }
+
+            let mut timeseries_plot = TimeseriesPlot::new(memory)
+                .group(timeseries_group)
+                .legend(legend.clone())
+                .height(PLOT_HEIGHT);
+            for field in plot.fields.iter() {
+                let values = fd.main_values.get(&field.name);
+                timeseries_plot = timeseries_plot.line(
+                    TimeseriesLine::new(field.name.clone()).color(field.color),
+                    times
+                        .iter()
+                        .copied()
+                        .zip(values.map(|s| s.iter().copied()).unwrap_or_default()),
+                );
+            }
+            ui.add(timeseries_plot);
+        }
+
+        match action {
+            Some(PlotAction::MoveUp(i)) if i > 0 => self.plots.swap(i, i - 1),
+            Some(PlotAction::MoveDown(i)) if i + 1 < self.plots.len() => self.plots.swap(i, i + 1),
+            Some(PlotAction::Remove(i)) => {
+                self.plots.remove(i);
+            }
+            _ => {}
+        }
+
+        if self.editing {
+            ui.add_space(8.0);
+            if ui.button("➕ Add plot").clicked() {
+                let title = format!("Plot {}", self.plots.len() + 1);
+                self.add_plot(DashboardPlot {
+                    title,
+                    fields: Vec::new(),
+                });
+            }
+        } else if self.plots.iter().all(|(plot, ..)| plot.fields.is_empty()) {
+            ui.label(RichText::new("Click \"✏ Edit\" to add fields to the plots.").weak());
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use egui_oszi::{TimeseriesGroup, TimeseriesLine, TimeseriesPlot, TimeseriesPlotMemory};
+use egui_plot::{Bar, BarChart, Corner, Legend};
+
+use crate::flight_data::FlightData;
+use crate::gui::colors::Colors;
+use crate::gui::flex::FlexColumns;
+use crate::loop_timing::{analyze_loop_timing, LoopTiming};
+use crate::utils::{execute_in_background, BackgroundCompStore};
+
+use super::{MIN_WIDE_WIDTH, PLOT_HEIGHT};
+
+/// Jitter above this share of the interval gets highlighted
+const JITTER_WARNING_RATIO: f64 = 0.1;
+/// Measured rate deviating from the configured one by more than this fraction gets highlighted
+const RATE_WARNING_DEVIATION: f64 = 0.02;
+
+fn format_us(seconds: f64) -> String {
+    format!("{:.1}µs", seconds * 1_000_000.0)
+}
+
+/// Intervals between the logged frames, to find loop time problems on overloaded boards.
+pub struct TimingTab {
+    fd: Arc<FlightData>,
+    interval_plot: TimeseriesPlotMemory<f64, f32>,
+    timing: BackgroundCompStore<Option<LoopTiming>>,
+}
+
+impl TimingTab {
+    pub fn new(fd: Arc<FlightData>) -> Self {
+        let (sender, receiver) = channel();
+        let fd_clone = fd.clone();
+        execute_in_background(async move {
+            let timing = analyze_loop_timing(&fd_clone.times, fd_clone.configured_log_rate());
+            let _ = sender.send(timing);
+        });
+
+        Self {
+            fd,
+            interval_plot: TimeseriesPlotMemory::new("intervals"),
+            timing: BackgroundCompStore::new(receiver),
+        }
+    }
+
+    fn show_summary(ui: &mut egui::Ui, fd: &FlightData, timing: &LoopTiming, colors: &Colors) {
+        egui::Grid::new(ui.next_auto_id())
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("PID loop");
+                ui.monospace(
+                    fd.pid_frequency()
+                        .map(|f| format!("{:.0}Hz", f))
+                        .unwrap_or("unknown".to_string()),
+                );
+                ui.end_row();
+
+                ui.label("Configured log rate");
+                ui.monospace(
+                    timing
+                        .configured_interval
+                        .map(|i| format!("{:.0}Hz ({})", 1.0 / i, format_us(i)))
+                        .unwrap_or("unknown".to_string()),
+                );
+                ui.end_row();
+
+                ui.label("Measured log rate");
+                let measured = format!(
+                    "{:.0}Hz ({})",
+                    1.0 / timing.median_interval,
+                    format_us(timing.median_interval)
+                );
+                let deviates = timing.configured_interval.is_some_and(|configured| {
+                    (timing.median_interval / configured - 1.0).abs() > RATE_WARNING_DEVIATION
+                });
+                if deviates {
+                    ui.colored_label(colors.error, format!("⚠ {}", measured))
+                        .on_hover_text("The log rate differs from the configured one");
+                } else {
+                    ui.monospace(measured);
+                }
+                ui.end_row();
+
+                ui.label("Mean interval");
+                ui.monospace(format_us(timing.mean_interval));
+                ui.end_row();
+
+                ui.label("Jitter (std)");
+                let jitter = format_us(timing.jitter);
+                if timing.jitter > JITTER_WARNING_RATIO * timing.median_interval {
+                    ui.colored_label(colors.error, format!("⚠ {}", jitter));
+                } else {
+                    ui.monospace(jitter);
+                }
+                ui.end_row();
+
+                ui.label("Min / p99 / max");
+                ui.monospace(format!(
+                    "{} / {} / {}",
+                    format_us(timing.min_interval),
+                    format_us(timing.p99_interval),
+                    format_us(timing.max_interval)
+                ));
+                ui.end_row();
+
+                ui.label("Missed frames");
+                let missed = format!(
+                    "{} in {} gaps ({:.2}%)",
+                    timing.missed_total(),
+                    timing.missed.len(),
+                    timing.missed_share(fd.times.len())
+                );
+                if timing.missed.is_empty() {
+                    ui.monospace(missed);
+                } else {
+                    ui.colored_label(colors.error, format!("⚠ {}", missed));
+                }
+                ui.end_row();
+            });
+    }
+
+    fn show_missed(ui: &mut egui::Ui, fd: &FlightData, timing: &LoopTiming) {
+        if timing.missed.is_empty() {
+            return;
+        }
+
+        let start = fd.times.first().copied().unwrap_or_default();
+        egui::ScrollArea::vertical()
+            .id_source("missed_frames")
+            .show(ui, |ui| {
+                egui::Grid::new(ui.next_auto_id())
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Time");
+                        ui.strong("Missed");
+                        ui.end_row();
+
+                        for missed in timing.missed.iter() {
+                            ui.monospace(format!("{:.3}s", missed.time - start));
+                            ui.monospace(format!("{}", missed.count));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    fn show_histogram(ui: &mut egui::Ui, timing: &LoopTiming, colors: &Colors) {
+        let bars = timing
+            .histogram
+            .iter()
+            .map(|(center, share)| {
+                Bar::new(center * 1_000_000.0, *share).width(timing.bin_width * 1_000_000.0)
+            })
+            .collect();
+
+        egui_plot::Plot::new(ui.next_auto_id())
+            .legend(Legend::default().position(Corner::RightTop))
+            .show_grid(true)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .y_axis_position(egui_plot::HPlacement::Right)
+            .y_axis_width(3)
+            .x_axis_formatter(|mark, _, _| format!("{}µs", mark.value))
+            .y_axis_formatter(|mark, _, _| format!("{}%", mark.value))
+            .height(PLOT_HEIGHT)
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(
+                    BarChart::new(bars)
+                        .name("Frame interval")
+                        .color(colors.loop_timing),
+                );
+            });
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, timeseries_group: &mut TimeseriesGroup) {
+        let Some(timing) = self.timing.get() else {
+            ui.spinner();
+            return;
+        };
+        let Some(timing) = timing.as_ref() else {
+            ui.label("Not enough frames for a timing analysis.");
+            return;
+        };
+
+        let colors = Colors::get(ui);
+        let fd = &self.fd;
+        let times = &fd.times;
+        let interval_plot = &mut self.interval_plot;
+
+        FlexColumns::new(MIN_WIDE_WIDTH)
+            .column(|ui| {
+                ui.vertical(|ui| {
+                    ui.heading("Frame Intervals");
+                    ui.add(
+                        TimeseriesPlot::new(interval_plot)
+                            .group(timeseries_group)
+                            .legend(Legend::default().position(Corner::LeftTop))
+                            .height(PLOT_HEIGHT)
+                            .line(
+                                TimeseriesLine::new("Interval (µs)").color(colors.loop_timing),
+                                times.iter().copied().zip(timing.intervals.iter().copied()),
+                            )
+                            .line(
+                                TimeseriesLine::new("Missed frames (gap, µs)").color(colors.error),
+                                times
+                                    .iter()
+                                    .copied()
+                                    .zip(timing.missed_indicator.iter().copied()),
+                            ),
+                    );
+
+                    ui.heading("Histogram");
+                    Self::show_histogram(ui, timing, &colors);
+                })
+                .response
+            })
+            .column(|ui| {
+                ui.vertical(|ui| {
+                    ui.heading("Loop Timing");
+                    Self::show_summary(ui, fd, timing, &colors);
+                    ui.separator();
+                    Self::show_missed(ui, fd, timing);
+                })
+                .response
+            })
+            .show(ui);
+    }
+}
@@ -1,21 +1,100 @@
+use std::ops::Range;
 use std::sync::mpsc::{channel, Sender};
 use std::sync::Arc;
 
+use egui::{Color32, RichText};
 use egui_oszi::{TimeseriesGroup, TimeseriesLine, TimeseriesPlot, TimeseriesPlotMemory};
-use egui_plot::{Corner, Legend, PlotPoints};
+use egui_plot::{Bar, BarChart, Corner, Legend, PlotPoints};
 
+use crate::feedforward::{analyze_feedforward, FeedforwardAnalysis, FeedforwardSettings};
 use crate::gui::colors::Colors;
 use crate::gui::flex::FlexColumns;
-use crate::step_response::calculate_step_response;
+use crate::iterm::{detect_windup, ItermAnalysis, ItermRelax};
+use crate::propwash::{detect_propwash, PropwashAnalysis};
+use crate::spectrum::coherence;
+use crate::step_response::{
+    average_step_response, calculate_latency, calculate_step_response_bands, Latency,
+};
+use crate::tracking_error::{
+    calculate_tracking_error, combined_rms, TrackingError, HISTOGRAM_BIN_WIDTH, HISTOGRAM_RANGE,
+};
 use crate::utils::execute_in_background;
 use crate::{flight_data::FlightData, utils::BackgroundCompStore};
 
-use super::{MIN_WIDE_WIDTH, PLOT_HEIGHT};
+use super::{AXIS_LABELS, MIN_WIDE_WIDTH, PLOT_HEIGHT};
+
+mod coherence;
+mod feedforward;
+mod iterm;
+mod pid_terms;
+mod rates;
+
+use coherence::{plot_coherence, reliable_bandwidth};
+use feedforward::show_feedforward;
+use iterm::show_iterm_windup;
+use pid_terms::PidTermsView;
+use rates::RatesView;
+
+const THROTTLE_BANDS: [(f32, f32); 4] = [
+    (0.0, 250.0),
+    (250.0, 500.0),
+    (500.0, 750.0),
+    (750.0, f32::INFINITY),
+];
+const THROTTLE_BAND_LABELS: [&str; 4] = ["0–25%", "25–50%", "50–75%", "75–100%"];
+
+/// Assigns a window to one of `THROTTLE_BANDS` by its mean throttle.
+fn throttle_band_classifier(throttle: &[f32]) -> impl Fn(Range<usize>) -> f32 + Copy + '_ {
+    move |range| {
+        let len = range.len() as f32;
+        throttle[range].iter().sum::<f32>() / len
+    }
+}
+
+const STICK_RATE_BANDS: [(f32, f32); 2] = [(0.0, 500.0), (500.0, f32::INFINITY)];
+const STICK_RATE_BAND_LABELS: [&str; 2] = ["<500°/s", "≥500°/s"];
+const COHERENCE_SEGMENT_SIZE: usize = 512;
+
+#[derive(PartialEq, Clone, Copy)]
+enum TuneView {
+    StepResponse,
+    TrackingError,
+    Coherence,
+    PidTerms,
+    ITerm,
+    Feedforward,
+    Rates,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum StepResponseSplit {
+    None,
+    Throttle,
+    StickRate,
+}
+
+struct AxisStepResponses {
+    overall: Vec<(f64, f64)>,
+    throttle_bands: Vec<Option<Vec<(f64, f64)>>>,
+    stick_rate_bands: Vec<Option<Vec<(f64, f64)>>>,
+    /// Setpoint to gyro coherence as (frequency, coherence)
+    coherence: Vec<(f64, f64)>,
+    /// Delay from setpoint to gyro, per throttle band
+    latency: Latency,
+}
 
 struct StepResponses {
-    roll_step_response: Vec<(f64, f64)>,
-    pitch_step_response: Vec<(f64, f64)>,
-    yaw_step_response: Vec<(f64, f64)>,
+    axes: [AxisStepResponses; 3],
+}
+
+type StepResponseCurve<'a> = (String, Color32, &'a [(f64, f64)]);
+
+/// Total duration of the sample `ranges` in seconds.
+fn ranges_duration(times: &[f64], ranges: &[Range<usize>]) -> f64 {
+    ranges
+        .iter()
+        .filter_map(|range| Some(times.get(range.end.checked_sub(1)?)? - times.get(range.start)?))
+        .sum()
 }
 
 pub struct TuneTab {
@@ -24,50 +103,257 @@ pub struct TuneTab {
     yaw_plot: TimeseriesPlotMemory<f64, f32>,
     fd: Arc<FlightData>,
     step_responses: BackgroundCompStore<StepResponses>,
+    step_response_split: StepResponseSplit,
+    propwash: BackgroundCompStore<PropwashAnalysis>,
+    tracking_errors: BackgroundCompStore<[TrackingError; 3]>,
+    pid_terms: PidTermsView,
+    iterm: BackgroundCompStore<ItermAnalysis>,
+    iterm_relax: ItermRelax,
+    feedforward: BackgroundCompStore<FeedforwardAnalysis>,
+    feedforward_settings: FeedforwardSettings,
+    rates: RatesView,
+    /// Sample ranges the analyses are restricted to
+    ranges: Vec<Range<usize>>,
+    view: TuneView,
 }
 
-const AXIS_LABELS: [&str; 3] = ["Roll", "Pitch", "Yaw"];
-
 impl TuneTab {
-    pub fn new(fd: Arc<FlightData>) -> Self {
-        // calculate step response in background thread
-        let (sender, receiver) = channel();
-        let step_responses = BackgroundCompStore::new(receiver);
-
-        Self::calculate_responses(fd.clone(), sender);
-        Self {
+    /// Analyses the samples within `ranges` of the flight, e.g. the selected flight phases.
+    pub fn new(fd: Arc<FlightData>, ranges: Vec<Range<usize>>) -> Self {
+        let mut tab = Self {
             roll_plot: TimeseriesPlotMemory::new("roll"),
             pitch_plot: TimeseriesPlotMemory::new("pitch"),
             yaw_plot: TimeseriesPlotMemory::new("yaw"),
-            step_responses,
+            step_responses: BackgroundCompStore::new(channel().1),
+            step_response_split: StepResponseSplit::None,
+            propwash: BackgroundCompStore::new(channel().1),
+            tracking_errors: BackgroundCompStore::new(channel().1),
+            pid_terms: PidTermsView::new(fd.clone(), ranges.clone()),
+            iterm: BackgroundCompStore::new(channel().1),
+            iterm_relax: ItermRelax::from_headers(&fd),
+            feedforward: BackgroundCompStore::new(channel().1),
+            feedforward_settings: FeedforwardSettings::from_headers(&fd),
+            rates: RatesView::new(fd.clone(), ranges.clone()),
+            ranges,
+            view: TuneView::StepResponse,
             fd,
-        }
+        };
+        tab.start_analyses();
+        tab
+    }
+
+    /// Restricts the analyses to other `ranges`, keeping the selected view and settings.
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.ranges = ranges;
+        self.start_analyses();
+        self.pid_terms
+            .set_ranges(self.fd.clone(), self.ranges.clone());
+        self.rates.set_ranges(self.fd.clone(), self.ranges.clone());
+    }
+
+    /// Runs the analyses in the background, replacing any earlier results.
+    fn start_analyses(&mut self) {
+        let (sender, receiver) = channel();
+        self.step_responses = BackgroundCompStore::new(receiver);
+        Self::calculate_responses(self.fd.clone(), self.ranges.clone(), sender);
+
+        let (sender, receiver) = channel();
+        self.propwash = BackgroundCompStore::new(receiver);
+        Self::calculate_propwash(self.fd.clone(), self.ranges.clone(), sender);
+
+        let (sender, receiver) = channel();
+        self.tracking_errors = BackgroundCompStore::new(receiver);
+        Self::calculate_tracking_errors(self.fd.clone(), self.ranges.clone(), sender);
+
+        let (sender, receiver) = channel();
+        self.iterm = BackgroundCompStore::new(receiver);
+        Self::calculate_iterm_windup(self.fd.clone(), self.ranges.clone(), sender);
+
+        let (sender, receiver) = channel();
+        self.feedforward = BackgroundCompStore::new(receiver);
+        Self::calculate_feedforward(self.fd.clone(), self.ranges.clone(), sender);
     }
 
-    fn calculate_responses(fd: Arc<FlightData>, sender: Sender<StepResponses>) {
+    fn calculate_responses(
+        fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
+        sender: Sender<StepResponses>,
+    ) {
         execute_in_background(async move {
             let empty_fallback = Vec::new();
             let setpoints = fd.setpoint().unwrap_or([&empty_fallback; 4]);
             let gyro = fd.gyro_filtered().unwrap_or([&empty_fallback; 3]);
             let sample_rate = fd.sample_rate();
-            let roll_step_response =
-                calculate_step_response(&fd.times, setpoints[0], gyro[0], sample_rate);
-            let pitch_step_response =
-                calculate_step_response(&fd.times, setpoints[1], gyro[1], sample_rate);
-            let yaw_step_response =
-                calculate_step_response(&fd.times, setpoints[2], gyro[2], sample_rate);
-            let _ = sender.send(StepResponses {
-                roll_step_response,
-                pitch_step_response,
-                yaw_step_response,
+            let throttle = setpoints[3];
+            let axes = [0, 1, 2].map(|i| {
+                let overall =
+                    average_step_response(&fd.times, setpoints[i], gyro[i], sample_rate, &ranges);
+                let throttle_bands = calculate_step_response_bands(
+                    &fd.times,
+                    setpoints[i],
+                    gyro[i],
+                    sample_rate,
+                    &ranges,
+                    &THROTTLE_BANDS,
+                    throttle_band_classifier(throttle),
+                );
+                let stick_rate_bands = calculate_step_response_bands(
+                    &fd.times,
+                    setpoints[i],
+                    gyro[i],
+                    sample_rate,
+                    &ranges,
+                    &STICK_RATE_BANDS,
+                    |range| {
+                        setpoints[i][range]
+                            .iter()
+                            .fold(0.0f32, |max, x| f32::max(max, x.abs()))
+                    },
+                );
+                let coherence = coherence(
+                    setpoints[i],
+                    gyro[i],
+                    &ranges,
+                    sample_rate,
+                    COHERENCE_SEGMENT_SIZE,
+                )
+                .unwrap_or_default();
+                let latency = calculate_latency(
+                    setpoints[i],
+                    gyro[i],
+                    sample_rate,
+                    &ranges,
+                    &THROTTLE_BANDS,
+                    throttle_band_classifier(throttle),
+                );
+                AxisStepResponses {
+                    overall,
+                    throttle_bands,
+                    stick_rate_bands,
+                    coherence,
+                    latency,
+                }
+            });
+            let _ = sender.send(StepResponses { axes });
+        });
+    }
+
+    fn calculate_propwash(
+        fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
+        sender: Sender<PropwashAnalysis>,
+    ) {
+        execute_in_background(async move {
+            let empty_fallback = Vec::new();
+            let setpoints = fd.setpoint().unwrap_or([&empty_fallback; 4]);
+            let gyro = fd.gyro_filtered().unwrap_or([&empty_fallback; 3]);
+            let analysis = detect_propwash(
+                &fd.times,
+                setpoints[3],
+                [setpoints[0], setpoints[1]],
+                [gyro[0], gyro[1]],
+                fd.sample_rate(),
+                &ranges,
+            );
+            let _ = sender.send(analysis);
+        });
+    }
+
+    fn calculate_tracking_errors(
+        fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
+        sender: Sender<[TrackingError; 3]>,
+    ) {
+        execute_in_background(async move {
+            let empty_fallback = Vec::new();
+            let setpoints = fd.setpoint().unwrap_or([&empty_fallback; 4]);
+            let gyro = fd.gyro_filtered().unwrap_or([&empty_fallback; 3]);
+            let errors = [0, 1, 2].map(|i| {
+                calculate_tracking_error(
+                    setpoints[i],
+                    gyro[i],
+                    setpoints[3],
+                    &ranges,
+                    &THROTTLE_BANDS,
+                    &STICK_RATE_BANDS,
+                )
             });
+            let _ = sender.send(errors);
+        });
+    }
+
+    fn calculate_iterm_windup(
+        fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
+        sender: Sender<ItermAnalysis>,
+    ) {
+        execute_in_background(async move {
+            let empty_fallback = Vec::new();
+            let setpoints = fd.setpoint().unwrap_or([&empty_fallback; 4]);
+            let iterm = fd.i().unwrap_or([&empty_fallback; 3]);
+            let analysis = detect_windup(
+                &fd.times,
+                iterm.map(|i| &i[..]),
+                [0, 1, 2].map(|i| &setpoints[i][..]),
+                &ranges,
+            );
+            let _ = sender.send(analysis);
+        });
+    }
+
+    fn calculate_feedforward(
+        fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
+        sender: Sender<FeedforwardAnalysis>,
+    ) {
+        execute_in_background(async move {
+            let empty_fallback = Vec::new();
+            let setpoints = fd.setpoint().unwrap_or([&empty_fallback; 4]);
+            let ff = fd.f().unwrap_or([&empty_fallback; 3]);
+            let gyro = fd.gyro_filtered().unwrap_or([&empty_fallback; 3]);
+            let analysis = analyze_feedforward(
+                &fd.times,
+                [0, 1, 2].map(|i| &setpoints[i][..]),
+                ff.map(|f| &f[..]),
+                gyro.map(|g| &g[..]),
+                fd.sample_rate(),
+                &ranges,
+            );
+            let _ = sender.send(analysis);
+        });
+    }
+
+    fn show_propwash_summary(ui: &mut egui::Ui, propwash: Option<&PropwashAnalysis>) {
+        ui.horizontal(|ui| {
+            ui.label("Propwash:");
+            let Some(propwash) = propwash else {
+                ui.spinner();
+                return;
+            };
+
+            ui.strong(format!("score {:.1}", propwash.score()))
+                .on_hover_text("Average oscillation (in °) per sharp throttle or rate drop");
+            ui.label(format!(
+                "{} events after {} manoeuvres, {:.2}s total",
+                propwash.events.len(),
+                propwash.manoeuvres,
+                propwash.total_duration()
+            ));
+            if let Some(worst) = propwash.worst_event() {
+                ui.label(format!(
+                    "worst at {:.2}s: {:.0}°/s for {:.0}ms",
+                    worst.start,
+                    worst.peak_amplitude,
+                    worst.duration() * 1000.0
+                ));
+            }
         });
     }
 
     pub fn plot_step_response(
         ui: &mut egui::Ui,
         i: usize,
-        step_response: &[(f64, f64)],
+        step_responses: &[StepResponseCurve],
         total_width: f32,
     ) -> egui::Response {
         let height = if ui.available_width() < total_width {
@@ -89,26 +375,209 @@ impl TuneTab {
             .y_axis_width(3)
             .height(height)
             .show(ui, |plot_ui| {
-                let points = PlotPoints::new(step_response.iter().map(|(x, y)| [*x, *y]).collect());
-                let egui_line = egui_plot::Line::new(points)
-                    .name(format!("Step Response ({})", AXIS_LABELS[i]))
-                    .color(egui::Color32::from_rgb(0xaf, 0x3a, 0x03))
-                    .width(2.0);
-                plot_ui.line(egui_line);
+                for (name, color, step_response) in step_responses.iter() {
+                    let points =
+                        PlotPoints::new(step_response.iter().map(|(x, y)| [*x, *y]).collect());
+                    let egui_line = egui_plot::Line::new(points)
+                        .name(name)
+                        .color(*color)
+                        .width(2.0);
+                    plot_ui.line(egui_line);
+                }
             })
             .response
     }
 
+    fn show_step_responses(
+        ui: &mut egui::Ui,
+        step_responses: &StepResponses,
+        split: &mut StepResponseSplit,
+        colors: &Colors,
+        total_width: f32,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Split by:");
+            ui.selectable_value(split, StepResponseSplit::None, "None");
+            ui.selectable_value(split, StepResponseSplit::Throttle, "Throttle");
+            ui.selectable_value(split, StepResponseSplit::StickRate, "Stick Rate");
+        });
+
+        Self::show_latency(ui, step_responses);
+
+        for (i, axis) in step_responses.axes.iter().enumerate() {
+            let label = AXIS_LABELS[i];
+            let (bands, band_labels): (_, &[&str]) = match *split {
+                StepResponseSplit::None => (&[][..], &[]),
+                StepResponseSplit::Throttle => (&axis.throttle_bands[..], &THROTTLE_BAND_LABELS),
+                StepResponseSplit::StickRate => {
+                    (&axis.stick_rate_bands[..], &STICK_RATE_BAND_LABELS)
+                }
+            };
+
+            let reliability = reliable_bandwidth(&axis.coherence)
+                .map(|f| format!(", reliable to {:.0}Hz", f))
+                .unwrap_or_default();
+            let curves: Vec<_> = if bands.is_empty() {
+                vec![(
+                    format!("Step Response ({}{})", label, reliability),
+                    Color32::from_rgb(0xaf, 0x3a, 0x03),
+                    &axis.overall[..],
+                )]
+            } else {
+                bands
+                    .iter()
+                    .zip(band_labels.iter())
+                    .zip(colors.quad.iter())
+                    .filter_map(|((band, band_label), color)| {
+                        band.as_ref().map(|response| {
+                            (format!("{} ({})", band_label, label), *color, &response[..])
+                        })
+                    })
+                    .collect()
+            };
+
+            Self::plot_step_response(ui, i, &curves, total_width);
+        }
+    }
+
+    fn show_latency(ui: &mut egui::Ui, step_responses: &StepResponses) {
+        let format_latency = |latency: Option<f64>| latency.map(|l| format!("{:.1}ms", l * 1000.0));
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Latency:");
+            for (label, axis) in AXIS_LABELS.iter().zip(step_responses.axes.iter()) {
+                let latency = format_latency(axis.latency.overall).unwrap_or("–".to_string());
+                ui.strong(format!("{} {}", label, latency));
+            }
+        })
+        .response
+        .on_hover_text("Delay from setpoint to gyro, from the cross-correlation of both");
+
+        egui::CollapsingHeader::new("Latency by throttle").show(ui, |ui| {
+            egui::Grid::new(ui.next_auto_id())
+                .num_columns(1 + THROTTLE_BAND_LABELS.len())
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Axis");
+                    for label in THROTTLE_BAND_LABELS.iter() {
+                        ui.strong(*label);
+                    }
+                    ui.end_row();
+
+                    for (label, axis) in AXIS_LABELS.iter().zip(step_responses.axes.iter()) {
+                        ui.label(*label);
+                        for band in axis.latency.bands.iter() {
+                            ui.monospace(format_latency(*band).unwrap_or_default());
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn show_tracking_errors(
+        ui: &mut egui::Ui,
+        tracking_errors: Option<&[TrackingError; 3]>,
+        colors: &Colors,
+        total_width: f32,
+    ) {
+        let Some(tracking_errors) = tracking_errors else {
+            ui.spinner();
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Overall RMS error:");
+            let axes: Vec<_> = tracking_errors.iter().collect();
+            ui.strong(format!("{:.1}°/s", combined_rms(&axes)))
+                .on_hover_text("Setpoint minus filtered gyro, over all axes");
+        });
+
+        egui::Grid::new(ui.next_auto_id())
+            .num_columns(2 + THROTTLE_BAND_LABELS.len() + STICK_RATE_BAND_LABELS.len())
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Axis");
+                ui.strong("RMS");
+                for label in THROTTLE_BAND_LABELS
+                    .iter()
+                    .chain(STICK_RATE_BAND_LABELS.iter())
+                {
+                    ui.strong(*label);
+                }
+                ui.end_row();
+
+                for (label, axis) in AXIS_LABELS.iter().zip(tracking_errors.iter()) {
+                    ui.label(*label);
+                    ui.monospace(format!("{:.1}", axis.rms));
+                    for band in axis
+                        .throttle_bands
+                        .iter()
+                        .chain(axis.stick_rate_bands.iter())
+                    {
+                        ui.monospace(band.map(|v| format!("{:.1}", v)).unwrap_or_default());
+                    }
+                    ui.end_row();
+                }
+            });
+
+        for (i, axis) in tracking_errors.iter().enumerate() {
+            let height = if ui.available_width() < total_width {
+                ui.available_height() / (3 - i) as f32
+            } else {
+                PLOT_HEIGHT
+            };
+
+            let bars = axis
+                .histogram
+                .iter()
+                .enumerate()
+                .map(|(bin, share)| {
+                    let center = -HISTOGRAM_RANGE + (bin as f32 + 0.5) * HISTOGRAM_BIN_WIDTH;
+                    Bar::new(center as f64, *share as f64).width(HISTOGRAM_BIN_WIDTH as f64)
+                })
+                .collect();
+
+            egui_plot::Plot::new(ui.next_auto_id())
+                .legend(Legend::default().position(Corner::RightTop))
+                .show_grid(true)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .link_axis("tracking_error", true, false)
+                .link_cursor("tracking_error", true, false)
+                .y_axis_position(egui_plot::HPlacement::Right)
+                .y_axis_width(3)
+                .x_axis_formatter(|mark, _, _| format!("{}°/s", mark.value))
+                .y_axis_formatter(|mark, _, _| format!("{}%", mark.value))
+                .height(height)
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(
+                        BarChart::new(bars)
+                            .name(format!("Error ({})", AXIS_LABELS[i]))
+                            .color(colors.tracking_error),
+                    );
+                });
+        }
+    }
+
     pub fn show(&mut self, ui: &mut egui::Ui, timeseries_group: &mut TimeseriesGroup) {
         if let Some(step_responses) = self.step_responses.get() {
             let total_width = ui.available_width();
             let times = &self.fd.times;
             let colors = Colors::get(ui);
+            let tracking_errors = self.tracking_errors.get().as_ref();
+            let iterm = self.iterm.get().as_ref();
+            let feedforward = self.feedforward.get().as_ref();
             FlexColumns::new(MIN_WIDE_WIDTH)
                 .column(|ui| {
                     ui.vertical(|ui| {
                         ui.heading("Time Domain");
 
+                        let propwash = self.propwash.get().as_ref();
+                        Self::show_propwash_summary(ui, propwash);
+                        let propwash_indicators = propwash.map(|p| &p.indicators);
+
                         let axes = [
                             &mut self.roll_plot,
                             &mut self.pitch_plot,
@@ -122,98 +591,161 @@ impl TuneTab {
                             };
 
                             let label = AXIS_LABELS[i];
-                            ui.add(
-                                TimeseriesPlot::new(plot)
-                                    .group(timeseries_group)
-                                    .legend(Legend::default().position(Corner::LeftTop))
-                                    .height(height)
-                                    .line(
-                                        TimeseriesLine::new(format!("Gyro ({}, unfilt.)", label))
-                                            .color(colors.gyro_unfiltered),
-                                        times.iter().copied().zip(
-                                            self.fd
-                                                .gyro_unfiltered()
-                                                .map(|s| s[i].iter().copied())
-                                                .unwrap_or_default(),
-                                        ),
-                                    )
-                                    .line(
-                                        TimeseriesLine::new(format!("Gyro ({})", label))
-                                            .color(colors.gyro_filtered),
-                                        times.iter().copied().zip(
-                                            self.fd
-                                                .gyro_filtered()
-                                                .map(|s| s[i].iter().copied())
-                                                .unwrap_or_default(),
-                                        ),
-                                    )
-                                    .line(
-                                        TimeseriesLine::new(format!("Setpoint ({})", label))
-                                            .color(colors.setpoint),
-                                        times.iter().copied().zip(
-                                            self.fd
-                                                .setpoint()
-                                                .map(|s| s[i].iter().copied())
-                                                .unwrap_or_default(),
-                                        ),
-                                    )
-                                    .line(
-                                        TimeseriesLine::new(format!("P ({})", label))
-                                            .color(colors.p),
-                                        times.iter().copied().zip(
-                                            self.fd
-                                                .p()
-                                                .map(|s| s[i].iter().copied())
-                                                .unwrap_or_default(),
-                                        ),
-                                    )
-                                    .line(
-                                        TimeseriesLine::new(format!("I ({})", label))
-                                            .color(colors.i),
-                                        times.iter().copied().zip(
-                                            self.fd
-                                                .i()
-                                                .map(|s| s[i].iter().copied())
-                                                .unwrap_or_default(),
-                                        ),
-                                    )
-                                    .line(
-                                        TimeseriesLine::new(format!("D ({})", label))
-                                            .color(colors.d),
-                                        times.iter().copied().zip(
-                                            self.fd.d()[i]
-                                                .map(|s| s.iter().copied())
-                                                .unwrap_or_default(),
-                                        ),
-                                    )
-                                    .line(
-                                        TimeseriesLine::new(format!("F ({})", label))
-                                            .color(colors.f),
-                                        times.iter().copied().zip(
-                                            self.fd
-                                                .f()
-                                                .map(|s| s[i].iter().copied())
-                                                .unwrap_or_default(),
-                                        ),
+                            let mut plot = TimeseriesPlot::new(plot)
+                                .group(timeseries_group)
+                                .legend(Legend::default().position(Corner::LeftTop))
+                                .height(height)
+                                .line(
+                                    TimeseriesLine::new(format!("Gyro ({}, unfilt.)", label))
+                                        .color(colors.gyro_unfiltered),
+                                    times.iter().copied().zip(
+                                        self.fd
+                                            .gyro_unfiltered()
+                                            .map(|s| s[i].iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("Gyro ({})", label))
+                                        .color(colors.gyro_filtered),
+                                    times.iter().copied().zip(
+                                        self.fd
+                                            .gyro_filtered()
+                                            .map(|s| s[i].iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("Setpoint ({})", label))
+                                        .color(colors.setpoint),
+                                    times.iter().copied().zip(
+                                        self.fd
+                                            .setpoint()
+                                            .map(|s| s[i].iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("P ({})", label)).color(colors.p),
+                                    times.iter().copied().zip(
+                                        self.fd
+                                            .p()
+                                            .map(|s| s[i].iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("I ({})", label)).color(colors.i),
+                                    times.iter().copied().zip(
+                                        self.fd
+                                            .i()
+                                            .map(|s| s[i].iter().copied())
+                                            .unwrap_or_default(),
                                     ),
-                            );
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("D ({})", label)).color(colors.d),
+                                    times.iter().copied().zip(
+                                        self.fd.d()[i]
+                                            .map(|s| s.iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("F ({})", label)).color(colors.f),
+                                    times.iter().copied().zip(
+                                        self.fd
+                                            .f()
+                                            .map(|s| s[i].iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("Error ({})", label))
+                                        .color(colors.tracking_error),
+                                    times.iter().copied().zip(
+                                        tracking_errors
+                                            .map(|e| e[i].error.iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("I windup ({})", label))
+                                        .color(colors.iterm_windup),
+                                    times.iter().copied().zip(
+                                        iterm
+                                            .map(|a| a.indicators[i].iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                )
+                                .line(
+                                    TimeseriesLine::new(format!("FF spikes ({})", label))
+                                        .color(colors.ff_spikes),
+                                    times.iter().copied().zip(
+                                        feedforward
+                                            .map(|a| a.axes[i].indicator.iter().copied())
+                                            .unwrap_or_default(),
+                                    ),
+                                );
+                            // propwash only shows up on roll and pitch
+                            if let Some(indicator) = propwash_indicators.and_then(|p| p.get(i)) {
+                                plot = plot.line(
+                                    TimeseriesLine::new(format!("Propwash ({})", label))
+                                        .color(colors.propwash),
+                                    times.iter().copied().zip(indicator.iter().copied()),
+                                );
+                            }
+                            ui.add(plot);
                         }
                     })
                     .response
                 })
                 .column(|ui| {
                     ui.vertical(|ui| {
-                        ui.heading("Step Response");
+                        ui.horizontal_wrapped(|ui| {
+                            let view = &mut self.view;
+                            for (value, label) in [
+                                (TuneView::StepResponse, "Step Response"),
+                                (TuneView::TrackingError, "Tracking Error"),
+                                (TuneView::Coherence, "Coherence"),
+                                (TuneView::PidTerms, "PID Terms"),
+                                (TuneView::ITerm, "I-Term"),
+                                (TuneView::Feedforward, "Feedforward"),
+                                (TuneView::Rates, "Rates"),
+                            ] {
+                                ui.selectable_value(view, value, RichText::new(label).heading());
+                            }
+                        });
 
-                        for (i, axis) in [
-                            &step_responses.roll_step_response,
-                            &step_responses.pitch_step_response,
-                            &step_responses.yaw_step_response,
-                        ]
-                        .iter()
-                        .enumerate()
-                        {
-                            Self::plot_step_response(ui, i, axis, total_width);
+                        match self.view {
+                            TuneView::StepResponse => Self::show_step_responses(
+                                ui,
+                                step_responses,
+                                &mut self.step_response_split,
+                                &colors,
+                                total_width,
+                            ),
+                            TuneView::TrackingError => Self::show_tracking_errors(
+                                ui,
+                                tracking_errors,
+                                &colors,
+                                total_width,
+                            ),
+                            TuneView::Coherence => {
+                                for (i, axis) in step_responses.axes.iter().enumerate() {
+                                    plot_coherence(ui, i, &axis.coherence, &colors, total_width);
+                                }
+                            }
+                            TuneView::PidTerms => self.pid_terms.show(ui, total_width),
+                            TuneView::ITerm => show_iterm_windup(ui, iterm, &self.iterm_relax),
+                            TuneView::Feedforward => show_feedforward(
+                                ui,
+                                feedforward,
+                                &self.feedforward_settings,
+                                ranges_duration(times, &self.ranges),
+                                self.fd.sample_rate(),
+                            ),
+                            TuneView::Rates => self.rates.show(ui, total_width),
                         }
                     })
                     .response
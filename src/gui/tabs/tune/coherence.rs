@@ -0,0 +1,88 @@
+use egui_plot::{Corner, Legend, PlotPoints, Polygon};
+
+use crate::gui::colors::Colors;
+
+use super::{AXIS_LABELS, PLOT_HEIGHT};
+
+/// Coherence below which the step response estimate at that frequency can't be trusted
+const RELIABLE_COHERENCE: f64 = 0.5;
+
+/// Frequency ranges in which the coherence is too low for a reliable step response.
+fn unreliable_ranges(coherence: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut ranges: Vec<(f64, f64)> = Vec::new();
+    let mut start = None;
+    for (f, c) in coherence.iter() {
+        match (*c < RELIABLE_COHERENCE, start) {
+            (true, None) => start = Some(*f),
+            (false, Some(s)) => {
+                ranges.push((s, *f));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let (Some(s), Some((last, _))) = (start, coherence.last()) {
+        ranges.push((s, *last));
+    }
+    ranges
+}
+
+/// Highest frequency up to which the setpoint excites the gyro well enough for the step
+/// response to be trusted, ignoring the DC bin.
+pub fn reliable_bandwidth(coherence: &[(f64, f64)]) -> Option<f64> {
+    let first_unreliable = coherence
+        .iter()
+        .skip(1)
+        .position(|(_, c)| *c < RELIABLE_COHERENCE)
+        .map(|i| i + 1)
+        .unwrap_or(coherence.len().checked_sub(1)?);
+    (first_unreliable > 1).then(|| coherence[first_unreliable].0)
+}
+
+pub fn plot_coherence(
+    ui: &mut egui::Ui,
+    i: usize,
+    coherence: &[(f64, f64)],
+    colors: &Colors,
+    total_width: f32,
+) {
+    let height = if ui.available_width() < total_width {
+        ui.available_height() / (3 - i) as f32
+    } else {
+        PLOT_HEIGHT
+    };
+
+    egui_plot::Plot::new(ui.next_auto_id())
+        .legend(Legend::default().position(Corner::RightTop))
+        .show_grid(true)
+        .include_y(0.0)
+        .include_y(1.0)
+        .link_axis("coherence", true, false)
+        .link_cursor("coherence", true, false)
+        .y_axis_position(egui_plot::HPlacement::Right)
+        .y_axis_width(3)
+        .x_axis_formatter(|mark, _, _| format!("{}Hz", mark.value))
+        .height(height)
+        .show(ui, |plot_ui| {
+            for (index, (low, high)) in unreliable_ranges(coherence).into_iter().enumerate() {
+                let points =
+                    PlotPoints::new(vec![[low, 0.0], [high, 0.0], [high, 1.0], [low, 1.0]]);
+                let mut polygon = Polygon::new(points)
+                    .fill_color(colors.unreliable.gamma_multiply(0.2))
+                    .stroke(egui::Stroke::NONE);
+                // only name the first one, so the legend has a single entry
+                if index == 0 {
+                    polygon = polygon.name("Unreliable");
+                }
+                plot_ui.polygon(polygon);
+            }
+
+            let points: PlotPoints = coherence.iter().map(|(f, c)| [*f, *c]).collect();
+            plot_ui.line(
+                egui_plot::Line::new(points)
+                    .name(format!("Coherence ({})", AXIS_LABELS[i]))
+                    .color(colors.coherence)
+                    .width(2.0),
+            );
+        });
+}
@@ -0,0 +1,141 @@
+use crate::feedforward::{FeedforwardAnalysis, FeedforwardSettings};
+use crate::gui::colors::Colors;
+
+use super::AXIS_LABELS;
+
+/// Jitter above this share of the FF RMS gets a hint to smooth FF more
+const JITTER_HINT_RATIO: f32 = 50.0;
+/// Spikes per minute from which a hint to raise the jitter reduction is shown
+const SPIKE_HINT_RATE: f64 = 10.0;
+/// Correlation with the setpoint derivative below which FF seems to be dominated by noise
+const CORRELATION_HINT: f32 = 0.5;
+
+fn show_settings(ui: &mut egui::Ui, settings: &FeedforwardSettings) {
+    let value = |v: Option<u8>| v.map(|v| v.to_string()).unwrap_or("?".to_string());
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Jitter factor:");
+        ui.strong(value(settings.jitter_factor));
+        ui.label("Smooth factor:");
+        ui.strong(value(settings.smooth_factor));
+        ui.label("Averaging:");
+        ui.strong(settings.averaging_name().unwrap_or("?"));
+        ui.label("Boost:");
+        ui.strong(value(settings.boost));
+        ui.label("Max rate limit:");
+        ui.strong(value(settings.max_rate_limit));
+    });
+}
+
+fn show_packet_rate(ui: &mut egui::Ui, analysis: &FeedforwardAnalysis, sample_rate: f64) {
+    ui.horizontal(|ui| {
+        ui.label("RC link packet rate:");
+        let Some(rate) = analysis.packet_rate else {
+            ui.strong("unknown")
+                .on_hover_text("Not enough stick movement to see the RC packets");
+            return;
+        };
+
+        let response = ui.strong(format!("{:.0}Hz", rate));
+        if rate >= 0.95 * sample_rate {
+            response.on_hover_text(
+                "The setpoint changes on every logged sample, the actual packet rate may be \
+                 higher than the log rate",
+            );
+        }
+        ui.label(format!("(jitter above {:.0}Hz)", analysis.jitter_cutoff));
+    });
+}
+
+fn show_axis_summary(ui: &mut egui::Ui, analysis: &FeedforwardAnalysis, duration: f64) {
+    egui::Grid::new(ui.next_auto_id())
+        .num_columns(6)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Axis");
+            ui.strong("Correlation")
+                .on_hover_text("How closely FF follows the setpoint derivative");
+            ui.strong("Jitter");
+            ui.strong("Jitter %");
+            ui.strong("Spikes")
+                .on_hover_text("FF spikes right after a setpoint step from the RC link");
+            ui.strong("Lead")
+                .on_hover_text("How far FF is ahead of the gyro");
+            ui.end_row();
+
+            for (label, axis) in AXIS_LABELS.iter().zip(analysis.axes.iter()) {
+                ui.label(*label);
+                ui.monospace(
+                    axis.correlation
+                        .map(|c| format!("{:.2}", c))
+                        .unwrap_or_default(),
+                );
+                ui.monospace(format!("{:.1}", axis.jitter));
+                ui.monospace(format!("{:.0}%", axis.jitter_ratio));
+                ui.monospace(format!(
+                    "{} ({:.1}/min)",
+                    axis.spikes.len(),
+                    spikes_per_minute(axis.spikes.len(), duration)
+                ));
+                ui.monospace(
+                    axis.lead
+                        .map(|l| format!("{:.1}ms", l * 1000.0))
+                        .unwrap_or_default(),
+                );
+                ui.end_row();
+            }
+        });
+}
+
+fn spikes_per_minute(spikes: usize, duration: f64) -> f64 {
+    if duration > 0.0 {
+        60.0 * spikes as f64 / duration
+    } else {
+        0.0
+    }
+}
+
+fn show_hints(ui: &mut egui::Ui, analysis: &FeedforwardAnalysis, duration: f64) {
+    let colors = Colors::get(ui);
+
+    for (label, axis) in AXIS_LABELS.iter().zip(analysis.axes.iter()) {
+        let mut hints = Vec::new();
+        if spikes_per_minute(axis.spikes.len(), duration) >= SPIKE_HINT_RATE {
+            hints.push("frequent FF spikes from RC steps, consider raising the jitter factor");
+        }
+        if axis.jitter_ratio >= JITTER_HINT_RATIO {
+            hints.push("jittery FF, consider raising the smooth factor or averaging");
+        }
+        if axis.correlation.is_some_and(|c| c < CORRELATION_HINT) {
+            hints.push("FF barely follows the setpoint derivative, check the RC link");
+        }
+        if axis.lead.is_some_and(|l| l < 0.0) {
+            hints.push("FF lags behind the gyro, it doesn't help the response");
+        }
+
+        for hint in hints {
+            ui.colored_label(colors.error, format!("⚠ {}: {}.", label, hint));
+        }
+    }
+}
+
+/// Quality of the feedforward signal, to tune FF smoothing and jitter reduction.
+pub fn show_feedforward(
+    ui: &mut egui::Ui,
+    analysis: Option<&FeedforwardAnalysis>,
+    settings: &FeedforwardSettings,
+    duration: f64,
+    sample_rate: f64,
+) {
+    show_settings(ui, settings);
+
+    let Some(analysis) = analysis else {
+        ui.spinner();
+        return;
+    };
+
+    show_packet_rate(ui, analysis, sample_rate);
+    show_axis_summary(ui, analysis, duration);
+    ui.separator();
+    show_hints(ui, analysis, duration);
+}
@@ -0,0 +1,110 @@
+use crate::iterm::{ItermAnalysis, ItermRelax, WindupKind};
+
+use super::AXIS_LABELS;
+
+/// Number of bounce-back events on an axis without I-term relax from which a hint is shown
+const RELAX_HINT_EVENTS: usize = 3;
+
+fn show_relax_settings(ui: &mut egui::Ui, relax: &ItermRelax) {
+    ui.horizontal(|ui| {
+        ui.label("I-term relax:");
+        ui.strong(relax.mode_name().unwrap_or("unknown"));
+        if relax.applies_to(0).unwrap_or(false) {
+            if let Some(relax_type) = relax.type_name() {
+                ui.label(format!("type {}", relax_type));
+            }
+            if let Some(cutoff) = relax.cutoff {
+                ui.label(format!("cutoff {}Hz", cutoff));
+            }
+        }
+    });
+}
+
+fn show_axis_summary(ui: &mut egui::Ui, analysis: &ItermAnalysis, relax: &ItermRelax) {
+    egui::Grid::new(ui.next_auto_id())
+        .num_columns(5)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Axis");
+            ui.strong("Relax");
+            ui.strong("Threshold");
+            ui.strong("Bounce-back");
+            ui.strong("Drift");
+            ui.end_row();
+
+            for (axis, label) in AXIS_LABELS.iter().enumerate() {
+                let count = |kind| {
+                    analysis
+                        .events
+                        .iter()
+                        .filter(|e| e.axis == axis && e.kind == kind)
+                        .count()
+                };
+                let relax_active = relax.applies_to(axis);
+
+                ui.label(*label);
+                ui.label(match relax_active {
+                    Some(true) => "On",
+                    Some(false) => "Off",
+                    None => "?",
+                });
+                ui.monospace(format!("{:.0}", analysis.thresholds[axis]));
+                let bounce_back = count(WindupKind::BounceBack);
+                let response = ui.monospace(format!("{}", bounce_back));
+                if relax_active == Some(false) && bounce_back >= RELAX_HINT_EVENTS {
+                    response.on_hover_text(
+                        "Frequent bounce-back with I-term relax disabled on this axis, \
+                         consider enabling it",
+                    );
+                }
+                ui.monospace(format!("{}", count(WindupKind::Drift)));
+                ui.end_row();
+            }
+        });
+}
+
+fn show_events(ui: &mut egui::Ui, analysis: &ItermAnalysis) {
+    if analysis.events.is_empty() {
+        ui.label("No I-term windup detected.");
+        return;
+    }
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new(ui.next_auto_id())
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Time");
+                ui.strong("Axis");
+                ui.strong("Kind");
+                ui.strong("Duration");
+                ui.strong("Peak I");
+                ui.strong("Max rate");
+                ui.end_row();
+
+                for event in analysis.events.iter() {
+                    ui.monospace(format!("{:.2}s", event.start));
+                    ui.label(AXIS_LABELS[event.axis]);
+                    ui.label(event.kind.to_string());
+                    ui.monospace(format!("{:.0}ms", (event.end - event.start) * 1000.0));
+                    ui.monospace(format!("{:.0}", event.peak));
+                    ui.monospace(format!("{:.0}°/s", event.max_setpoint_rate));
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+/// I-term windup events together with the I-term relax settings they might be caused by.
+pub fn show_iterm_windup(ui: &mut egui::Ui, analysis: Option<&ItermAnalysis>, relax: &ItermRelax) {
+    show_relax_settings(ui, relax);
+
+    let Some(analysis) = analysis else {
+        ui.spinner();
+        return;
+    };
+
+    show_axis_summary(ui, analysis, relax);
+    ui.separator();
+    show_events(ui, analysis);
+}
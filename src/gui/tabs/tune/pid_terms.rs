@@ -0,0 +1,173 @@
+use std::ops::Range;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use egui::Color32;
+use egui_plot::{Corner, Legend, PlotPoints};
+
+use crate::flight_data::FlightData;
+use crate::gui::colors::Colors;
+use crate::pid_contribution::{
+    calculate_pid_contribution, PidTerm, TermContribution, NOISE_FREQUENCY,
+};
+use crate::utils::{execute_in_background, BackgroundCompStore};
+
+use super::{AXIS_LABELS, PLOT_HEIGHT};
+
+#[derive(PartialEq, Clone, Copy)]
+enum PidTermsDisplay {
+    Time,
+    Spectrum,
+}
+
+/// Share of each PID term in the total output per axis, over time and by frequency.
+pub struct PidTermsView {
+    contributions: BackgroundCompStore<[Vec<TermContribution>; 3]>,
+    display: PidTermsDisplay,
+}
+
+impl PidTermsView {
+    pub fn new(fd: Arc<FlightData>, ranges: Vec<Range<usize>>) -> Self {
+        Self {
+            contributions: Self::calculate(fd, ranges),
+            display: PidTermsDisplay::Time,
+        }
+    }
+
+    /// Recalculates the contributions for other `ranges`, keeping the display.
+    pub fn set_ranges(&mut self, fd: Arc<FlightData>, ranges: Vec<Range<usize>>) {
+        self.contributions = Self::calculate(fd, ranges);
+    }
+
+    fn calculate(
+        fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
+    ) -> BackgroundCompStore<[Vec<TermContribution>; 3]> {
+        let (sender, receiver) = channel();
+        execute_in_background(async move {
+            let sample_rate = fd.sample_rate();
+            let contributions = [0, 1, 2].map(|axis| {
+                let terms: Vec<_> = PidTerm::ALL
+                    .iter()
+                    .filter_map(|term| {
+                        let values = match term {
+                            PidTerm::P => fd.p().map(|p| p[axis]),
+                            PidTerm::I => fd.i().map(|i| i[axis]),
+                            PidTerm::D => fd.d()[axis],
+                            PidTerm::F => fd.f().map(|f| f[axis]),
+                        }?;
+                        Some((*term, &values[..]))
+                    })
+                    .collect();
+                calculate_pid_contribution(&fd.times, &terms, sample_rate, &ranges)
+            });
+            let _ = sender.send(contributions);
+        });
+        BackgroundCompStore::new(receiver)
+    }
+
+    fn term_color(colors: &Colors, term: PidTerm) -> Color32 {
+        match term {
+            PidTerm::P => colors.p,
+            PidTerm::I => colors.i,
+            PidTerm::D => colors.d,
+            PidTerm::F => colors.f,
+        }
+    }
+
+    fn show_summary(ui: &mut egui::Ui, contributions: &[Vec<TermContribution>; 3]) {
+        egui::Grid::new(ui.next_auto_id())
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Axis");
+                ui.strong("Term");
+                ui.strong("RMS");
+                ui.strong("Share");
+                ui.strong(format!(">{:.0}Hz", NOISE_FREQUENCY))
+                    .on_hover_text("Share of the term's power above this frequency, mostly noise");
+                ui.end_row();
+
+                for (label, terms) in AXIS_LABELS.iter().zip(contributions.iter()) {
+                    for contribution in terms.iter() {
+                        ui.label(*label);
+                        ui.label(contribution.term.name());
+                        ui.monospace(format!("{:.1}", contribution.rms));
+                        ui.monospace(format!("{:.0}%", contribution.share));
+                        ui.monospace(
+                            contribution
+                                .noise_share
+                                .map(|s| format!("{:.0}%", s))
+                                .unwrap_or_default(),
+                        );
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, total_width: f32) {
+        let colors = Colors::get(ui);
+
+        ui.horizontal(|ui| {
+            ui.label("Show:");
+            ui.selectable_value(&mut self.display, PidTermsDisplay::Time, "Over Time");
+            ui.selectable_value(&mut self.display, PidTermsDisplay::Spectrum, "Spectrum");
+        });
+
+        let Some(contributions) = self.contributions.get() else {
+            ui.spinner();
+            return;
+        };
+
+        egui::CollapsingHeader::new("Summary")
+            .default_open(true)
+            .show(ui, |ui| Self::show_summary(ui, contributions));
+
+        for (i, terms) in contributions.iter().enumerate() {
+            let height = if ui.available_width() < total_width {
+                ui.available_height() / (3 - i) as f32
+            } else {
+                PLOT_HEIGHT
+            };
+
+            let (link, unit) = match self.display {
+                PidTermsDisplay::Time => ("pid_terms_time", "%"),
+                PidTermsDisplay::Spectrum => ("pid_terms_spectrum", "dB"),
+            };
+
+            egui_plot::Plot::new(ui.next_auto_id())
+                .legend(Legend::default().position(Corner::RightTop))
+                .show_grid(true)
+                .link_axis(link, true, true)
+                .link_cursor(link, true, true)
+                .y_axis_position(egui_plot::HPlacement::Right)
+                .y_axis_width(3)
+                .y_axis_formatter(move |mark, _, _| format!("{}{}", mark.value, unit))
+                .height(height)
+                .show(ui, |plot_ui| {
+                    for contribution in terms.iter() {
+                        let points: PlotPoints = match self.display {
+                            PidTermsDisplay::Time => contribution
+                                .share_over_time
+                                .iter()
+                                .map(|(t, share)| [*t, *share])
+                                .collect(),
+                            PidTermsDisplay::Spectrum => contribution
+                                .spectrum
+                                .iter()
+                                .flat_map(|s| s.decibels())
+                                .map(|(f, db)| [f, db])
+                                .collect(),
+                        };
+                        let name = format!("{} ({})", contribution.term.name(), AXIS_LABELS[i]);
+                        plot_ui.line(
+                            egui_plot::Line::new(points)
+                                .name(name)
+                                .color(Self::term_color(&colors, contribution.term)),
+                        );
+                    }
+                });
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::ops::Range;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use egui_plot::{Corner, Legend, PlotPoints, Points};
+
+use crate::flight_data::FlightData;
+use crate::gui::colors::Colors;
+use crate::iter::values_in_ranges;
+use crate::rates::{compare_rates, stick_deflection, RatesComparison, RatesConfig, RatesMismatch};
+use crate::utils::{execute_in_background, BackgroundCompStore};
+
+use super::{AXIS_LABELS, PLOT_HEIGHT};
+
+/// Upper limit for the number of scatter points drawn per axis
+const MAX_SCATTER_POINTS: usize = 5000;
+const CURVE_POINTS: usize = 100;
+
+struct RatesAxis {
+    /// (stick deflection, absolute setpoint) samples
+    scatter: Vec<[f64; 2]>,
+    comparison: Option<RatesComparison>,
+}
+
+/// Rates curve actually flown, reconstructed from `rcCommand` and `setpoint`, compared against
+/// the one configured in the headers.
+pub struct RatesView {
+    config: Option<Arc<RatesConfig>>,
+    axes: BackgroundCompStore<Vec<RatesAxis>>,
+}
+
+impl RatesView {
+    pub fn new(fd: Arc<FlightData>, ranges: Vec<Range<usize>>) -> Self {
+        let config = RatesConfig::from_headers(&fd).map(Arc::new);
+        Self {
+            axes: Self::calculate(fd, config.clone(), ranges),
+            config,
+        }
+    }
+
+    /// Recalculates the flown curve for other `ranges`.
+    pub fn set_ranges(&mut self, fd: Arc<FlightData>, ranges: Vec<Range<usize>>) {
+        self.axes = Self::calculate(fd, self.config.clone(), ranges);
+    }
+
+    fn calculate(
+        fd: Arc<FlightData>,
+        config: Option<Arc<RatesConfig>>,
+        ranges: Vec<Range<usize>>,
+    ) -> BackgroundCompStore<Vec<RatesAxis>> {
+        let (sender, receiver) = channel();
+        execute_in_background(async move {
+            let (Some(rc_command), Some(setpoint)) = (fd.rc_command(), fd.setpoint()) else {
+                let _ = sender.send(Vec::new());
+                return;
+            };
+
+            let axes = (0..3)
+                .map(|axis| {
+                    let rc_command = values_in_ranges(rc_command[axis], &ranges);
+                    let setpoint = values_in_ranges(setpoint[axis], &ranges);
+                    let step = usize::max(1, rc_command.len() / MAX_SCATTER_POINTS);
+                    let scatter = rc_command
+                        .iter()
+                        .zip(setpoint.iter())
+                        .step_by(step)
+                        .map(|(rc, sp)| [stick_deflection(*rc) as f64, sp.abs() as f64])
+                        .collect();
+                    let comparison = config
+                        .as_ref()
+                        .and_then(|config| compare_rates(config, axis, &rc_command, &setpoint));
+                    RatesAxis {
+                        scatter,
+                        comparison,
+                    }
+                })
+                .collect();
+            let _ = sender.send(axes);
+        });
+        BackgroundCompStore::new(receiver)
+    }
+
+    fn show_config(&self, ui: &mut egui::Ui) {
+        let Some(config) = &self.config else {
+            ui.label("No rates found in the headers, only showing the measured curve.");
+            return;
+        };
+
+        egui::Grid::new(ui.next_auto_id())
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong(format!("{:?}", config.rates_type));
+                ui.strong("RC Rate");
+                ui.strong("Rate");
+                ui.strong("Expo");
+                ui.end_row();
+
+                for (label, axis) in AXIS_LABELS.iter().zip(config.axes.iter()) {
+                    ui.label(*label);
+                    ui.monospace(format!("{}", axis.rc_rate));
+                    ui.monospace(format!("{}", axis.rate));
+                    ui.monospace(format!("{}", axis.expo));
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_mismatches(ui: &mut egui::Ui, axes: &[RatesAxis], colors: &Colors) {
+        for (label, axis) in AXIS_LABELS.iter().zip(axes.iter()) {
+            let Some(comparison) = &axis.comparison else {
+                continue;
+            };
+
+            for mismatch in comparison.mismatches.iter() {
+                let text = match mismatch {
+                    RatesMismatch::Curve => format!(
+                        "{}: flown rates deviate by {:+.0}°/s at {:.0}% stick from the \
+                         configured curve, check expo and rates.",
+                        label,
+                        comparison.max_deviation.0,
+                        comparison.max_deviation.1 * 100.0
+                    ),
+                    RatesMismatch::Smoothing => format!(
+                        "{}: setpoint scatters by {:.0}°/s around the curve, likely caused by \
+                         RC smoothing.",
+                        label, comparison.scatter
+                    ),
+                };
+                ui.colored_label(colors.error, format!("⚠ {}", text));
+            }
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, total_width: f32) {
+        let colors = Colors::get(ui);
+        self.show_config(ui);
+
+        let Some(axes) = self.axes.get() else {
+            ui.spinner();
+            return;
+        };
+        Self::show_mismatches(ui, axes, &colors);
+
+        for (i, axis) in axes.iter().enumerate() {
+            let height = if ui.available_width() < total_width {
+                ui.available_height() / (3 - i) as f32
+            } else {
+                PLOT_HEIGHT
+            };
+
+            let curve = self
+                .config
+                .as_ref()
+                .and_then(|config| config.curve(i, CURVE_POINTS));
+
+            egui_plot::Plot::new(ui.next_auto_id())
+                .legend(Legend::default().position(Corner::LeftTop))
+                .show_grid(true)
+                .include_x(0.0)
+                .include_x(1.0)
+                .include_y(0.0)
+                .link_axis("rates", true, false)
+                .link_cursor("rates", true, false)
+                .y_axis_position(egui_plot::HPlacement::Right)
+                .y_axis_width(4)
+                .x_axis_formatter(|mark, _, _| format!("{:.0}%", mark.value * 100.0))
+                .y_axis_formatter(|mark, _, _| format!("{}°/s", mark.value))
+                .height(height)
+                .show(ui, |plot_ui| {
+                    plot_ui.points(
+                        Points::new(PlotPoints::new(axis.scatter.clone()))
+                            .name(format!("Flown ({})", AXIS_LABELS[i]))
+                            .color(colors.setpoint.gamma_multiply(0.3))
+                            .radius(1.0),
+                    );
+                    if let Some(curve) = &curve {
+                        let points: PlotPoints = curve.iter().map(|(x, y)| [*x, *y]).collect();
+                        plot_ui.line(
+                            egui_plot::Line::new(points)
+                                .name("Configured")
+                                .color(colors.rates)
+                                .width(2.0),
+                        );
+                    }
+                });
+        }
+    }
+}
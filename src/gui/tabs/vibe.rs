@@ -1,26 +1,47 @@
 use std::f32::consts::PI;
+use std::ops::Range;
 use std::sync::mpsc::{channel, Receiver, TryRecvError};
 use std::sync::{Arc, OnceLock};
 
 use egui::{Color32, DragValue};
+use egui_plot::{Line, LineStyle, PlotPoints};
 use itertools::Itertools;
 
 use crate::flight_data::FlightData;
 use crate::gui::flex::*;
-use crate::iter::IterExt;
-use crate::utils::execute_in_background;
+use crate::iter::{values_in_ranges, windows_in};
+use crate::noise_sources::{identify_noise_sources, throttle_band_means, NoiseFinding};
+use crate::utils::{execute_in_background, BackgroundCompStore};
 
-use super::{MIN_WIDE_WIDTH, PLOT_HEIGHT};
+use super::{AXIS_LABELS, MIN_WIDE_WIDTH, PLOT_HEIGHT};
+
+mod accel_vibration;
+mod noise_summary;
+mod psd;
+
+use accel_vibration::AccelVibration;
+use noise_summary::NoiseSummary;
+use psd::{PsdSeries, PsdView};
 
 const COLORGRAD_LOOKUP_SIZE: usize = 128;
 const TIME_DOMAIN_TEX_WIDTH: usize = 1024;
 const THROTTLE_DOMAIN_BUCKETS: usize = 256;
 const FFT_SIZE_OPTIONS: [usize; 4] = [256, 512, 1024, 2048];
+const MAX_LISTED_NOISE_FINDINGS: usize = 10;
+const TIME_OVERLAY_POINTS: usize = 2048;
+const THROTTLE_OVERLAY_BUCKETS: usize = 32;
+const HARMONIC_LABELS: [&str; 3] = ["1st harmonic", "2nd harmonic", "3rd harmonic"];
+const HARMONIC_STYLES: [LineStyle; 3] = [
+    LineStyle::Solid,
+    LineStyle::Dashed { length: 10.0 },
+    LineStyle::Dotted { spacing: 5.0 },
+];
 
 #[derive(PartialEq, Clone, Copy)]
 enum VibeDomain {
     Time,
     Throttle,
+    Frequency,
 }
 
 #[derive(PartialEq, Clone, Copy, Default, Debug)]
@@ -47,6 +68,7 @@ struct FftSettings {
     pub step_size: usize,
     pub plot_colorscheme: Colorscheme,
     pub plot_max: f32,
+    pub rpm_harmonics: [bool; 3],
     color_lookup_table: Option<(Colorscheme, [Color32; COLORGRAD_LOOKUP_SIZE])>,
 }
 
@@ -98,13 +120,94 @@ impl Default for FftSettings {
             step_size: 8,
             plot_colorscheme: Colorscheme::default(),
             plot_max: 10.0,
+            rpm_harmonics: [true, false, false],
             color_lookup_table: None,
         }
     }
 }
 
+/// Mean motor frequency over time and throttle (in the selected `ranges`), reduced to a
+/// reasonable number of points for drawing the RPM harmonics on top of the spectrograms.
+struct MotorFrequencies {
+    time: Vec<(f64, f64)>,
+    throttle: Vec<(f64, f64)>,
+}
+
+impl MotorFrequencies {
+    pub fn new(fd: &FlightData, ranges: &[Range<usize>]) -> Option<Self> {
+        let frequency = fd.motor_frequency()?;
+        let throttle = fd.setpoint()?[3];
+
+        let step = usize::max(1, frequency.len() / TIME_OVERLAY_POINTS);
+        let time = fd
+            .times
+            .chunks(step)
+            .zip(frequency.chunks(step))
+            .map(|(t, f)| (t[0], (f.iter().sum::<f32>() / (f.len() as f32)) as f64))
+            .collect();
+
+        let mut buckets = [(0.0, 0usize); THROTTLE_OVERLAY_BUCKETS];
+        let throttle = values_in_ranges(throttle, ranges);
+        let frequency = values_in_ranges(&frequency, ranges);
+        for (t, f) in throttle.iter().zip(frequency.iter()) {
+            let i = ((t / 1000.0) * THROTTLE_OVERLAY_BUCKETS as f32) as usize;
+            let bucket = &mut buckets[usize::min(i, THROTTLE_OVERLAY_BUCKETS - 1)];
+            bucket.0 += *f as f64;
+            bucket.1 += 1;
+        }
+        let throttle = buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, count))| *count > 0)
+            .map(|(i, (sum, count))| {
+                let x = ((i as f64) + 0.5) / (THROTTLE_OVERLAY_BUCKETS as f64);
+                (x, sum / (*count as f64))
+            })
+            .collect();
+
+        Some(Self { time, throttle })
+    }
+
+    /// Draws the enabled harmonics of the given (x, frequency) points into a spectrogram,
+    /// leaving out the parts above the Nyquist frequency.
+    pub fn plot_harmonics(
+        plot_ui: &mut egui_plot::PlotUi,
+        points: &[(f64, f64)],
+        harmonics: &[bool; 3],
+        max_freq: f64,
+    ) {
+        for (h, _) in harmonics
+            .iter()
+            .enumerate()
+            .filter(|(_, enabled)| **enabled)
+        {
+            let factor = ((h + 1) as f64) / max_freq;
+
+            let mut segments: Vec<Vec<[f64; 2]>> = vec![Vec::new()];
+            for (x, frequency) in points.iter() {
+                let y = frequency * factor;
+                if y <= 1.0 {
+                    segments.last_mut().unwrap().push([*x, y]);
+                } else if !segments.last().unwrap().is_empty() {
+                    segments.push(Vec::new());
+                }
+            }
+
+            for segment in segments.into_iter().filter(|s| !s.is_empty()) {
+                let line = Line::new(PlotPoints::new(segment))
+                    .name(HARMONIC_LABELS[h])
+                    .color(Color32::WHITE)
+                    .style(HARMONIC_STYLES[h]);
+                plot_ui.line(line);
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 struct FftChunk {
+    /// Index of the selected range the chunk lies in
+    segment: usize,
     time: f64,
     fft: Vec<f32>,
     throttle: f32,
@@ -151,6 +254,7 @@ impl FftChunk {
             .collect();
 
         Self {
+            segment: 0,
             time,
             fft,
             throttle,
@@ -158,7 +262,7 @@ impl FftChunk {
     }
 }
 
-type FftAxisValueCallback = Box<fn(&FlightData) -> [Option<&Vec<f32>>; 3]>;
+type FftAxisValueCallback = Arc<dyn Fn(&FlightData) -> [Option<&Vec<f32>>; 3] + Send + Sync>;
 
 struct FftAxis {
     ctx: egui::Context,
@@ -166,14 +270,18 @@ struct FftAxis {
 
     i: usize,
     flight_data: Arc<FlightData>,
+    ranges: Vec<Range<usize>>,
     value_callback: FftAxisValueCallback,
 
-    chunks: Vec<FftChunk>,
+    chunks: Arc<Vec<FftChunk>>,
     chunk_receiver: Option<Receiver<Vec<FftChunk>>>,
     time_textures: Vec<(f64, f64, egui::TextureHandle)>,
     time_texture_receiver: Option<Receiver<(f64, f64, egui::TextureHandle)>>,
     throttle_texture: Option<egui::TextureHandle>,
     throttle_texture_receiver: Option<Receiver<egui::TextureHandle>>,
+
+    identify_noise: bool,
+    noise_findings: BackgroundCompStore<Vec<NoiseFinding>>,
 }
 
 impl FftAxis {
@@ -182,7 +290,8 @@ impl FftAxis {
         fft_settings: FftSettings,
         i: usize,
         flight_data: Arc<FlightData>,
-        value_callback: fn(&FlightData) -> [Option<&Vec<f32>>; 3],
+        ranges: Vec<Range<usize>>,
+        value_callback: FftAxisValueCallback,
     ) -> Self {
         let mut new = Self {
             ctx: ctx.clone(),
@@ -190,14 +299,18 @@ impl FftAxis {
 
             i,
             flight_data,
-            value_callback: Box::new(value_callback),
+            ranges,
+            value_callback,
 
-            chunks: Vec::new(),
+            chunks: Arc::default(),
             chunk_receiver: None,
             time_textures: Vec::new(),
             time_texture_receiver: None,
             throttle_texture: None,
             throttle_texture_receiver: None,
+
+            identify_noise: false,
+            noise_findings: BackgroundCompStore::new(channel().1),
         };
         new.recalculate_ffts();
         new
@@ -224,12 +337,14 @@ impl FftAxis {
     pub fn recalculate_ffts(&mut self) {
         let (chunk_sender, chunk_receiver) = channel();
 
-        self.chunks.truncate(0);
+        self.chunks = Arc::default();
         self.chunk_receiver = Some(chunk_receiver);
         self.time_textures.truncate(0);
         self.throttle_texture = None;
+        self.noise_findings = BackgroundCompStore::new(channel().1);
 
         let fd = self.flight_data.clone();
+        let ranges = self.ranges.clone();
         let cb = self.value_callback.clone();
         let i = self.i;
         let fft_size = self.fft_settings.size;
@@ -238,25 +353,23 @@ impl FftAxis {
         execute_in_background(async move {
             let throttle = &fd.setpoint().unwrap()[3];
             let Some(values) = &cb(&fd)[i] else { return };
-            let time_windows = fd
-                .times
-                .iter()
-                .copied()
-                .overlapping_windows(fft_size, fft_step_size);
-            let data_windows = values
-                .iter()
-                .copied()
-                .overlapping_windows(fft_size, fft_step_size);
-            let throttle_windows = throttle
+            let len = fd.times.len().min(values.len()).min(throttle.len());
+
+            // windows never span the gap between two selected ranges
+            ranges
                 .iter()
-                .copied()
-                .overlapping_windows(fft_size, fft_step_size);
-
-            time_windows
-                .zip(data_windows.zip(throttle_windows))
-                .filter(|(time, _)| time.len() == fft_size)
-                .map(|(time, (data, throttle))| {
-                    FftChunk::calculate(time[0], &data, throttle[throttle.len() / 2])
+                .enumerate()
+                .flat_map(|(segment, range)| {
+                    let range = range.start.min(len)..range.end.min(len);
+                    windows_in(range, fft_size, fft_step_size).map(move |w| (segment, w))
+                })
+                .map(|(segment, window)| FftChunk {
+                    segment,
+                    ..FftChunk::calculate(
+                        fd.times[window.start],
+                        &values[window.clone()],
+                        throttle[window.start + fft_size / 2],
+                    )
                 })
                 .chunks(100)
                 .into_iter()
@@ -276,12 +389,15 @@ impl FftAxis {
 
         let fft_size = self.fft_settings.size;
 
-        let chunks = self.chunks.clone(); // TODO
+        let chunks = self.chunks.clone();
         let mut fft_settings = self.fft_settings.clone();
         let fft_max = self.fft_settings.plot_max;
         let ctx = self.ctx.clone();
         execute_in_background(async move {
-            for (i, columns) in chunks.chunks(TIME_DOMAIN_TEX_WIDTH).enumerate() {
+            let columns = chunks
+                .chunk_by(|a, b| a.segment == b.segment)
+                .flat_map(|segment| segment.chunks(TIME_DOMAIN_TEX_WIDTH));
+            for (i, columns) in columns.enumerate() {
                 let image = Self::create_image(columns, fft_max, &mut fft_settings);
                 let tex_handle =
                     ctx.load_texture(format!("tex_{:?}", i), image, Default::default());
@@ -292,15 +408,14 @@ impl FftAxis {
             }
         });
 
-        let chunks = self.chunks.clone(); // TODO
+        let chunks = self.chunks.clone();
         let mut fft_settings = self.fft_settings.clone();
         let fft_max = self.fft_settings.plot_max;
         let ctx = self.ctx.clone();
         execute_in_background(async move {
-            const ARRAY_REPEAT_VALUE: Vec<FftChunk> = Vec::new();
-            let mut throttle_buckets: [Vec<FftChunk>; THROTTLE_DOMAIN_BUCKETS] =
-                [ARRAY_REPEAT_VALUE; THROTTLE_DOMAIN_BUCKETS];
-            for chunk in chunks {
+            let mut throttle_buckets: [Vec<&FftChunk>; THROTTLE_DOMAIN_BUCKETS] =
+                std::array::from_fn(|_| Vec::new());
+            for chunk in chunks.iter() {
                 let bucket_i =
                     ((chunk.throttle / 1000.0) * THROTTLE_DOMAIN_BUCKETS as f32) as usize;
                 let bucket_i = usize::min(bucket_i, THROTTLE_DOMAIN_BUCKETS - 1);
@@ -312,10 +427,10 @@ impl FftAxis {
                 let size = bucket.len();
                 let avg = bucket
                     .into_iter()
-                    .map(|chunk| chunk.fft)
+                    .map(|chunk| &chunk.fft)
                     .fold(vec![0f32; fft_size / 2], |a, b| {
                         a.into_iter()
-                            .zip(b.into_iter())
+                            .zip(b.iter().copied())
                             .map(|(a, b)| {
                                 if a.is_normal() && b.is_normal() {
                                     a + b
@@ -354,12 +469,43 @@ impl FftAxis {
         self.throttle_texture_receiver = Some(throttle_texture_receiver);
     }
 
+    pub fn identify_noise_sources(&mut self) {
+        let (sender, receiver) = channel();
+        self.noise_findings = BackgroundCompStore::new(receiver);
+
+        let chunks = self.chunks.clone();
+        let fd = self.flight_data.clone();
+        let ranges = self.ranges.clone();
+        let resolution = fd.sample_rate() / (self.fft_settings.size as f64);
+        let ctx = self.ctx.clone();
+        execute_in_background(async move {
+            let motor_frequencies =
+                fd.setpoint()
+                    .zip(fd.motor_frequency())
+                    .map(|(setpoint, frequency)| {
+                        throttle_band_means(
+                            &values_in_ranges(setpoint[3], &ranges),
+                            &values_in_ranges(&frequency, &ranges),
+                        )
+                    });
+
+            // chunks are stored as log10 of the power, highest frequency first
+            let spectra = chunks
+                .iter()
+                .map(|chunk| (chunk.throttle, chunk.fft.iter().rev().map(|v| 10.0 * v)));
+            let findings = identify_noise_sources(spectra, resolution, motor_frequencies.as_ref());
+
+            let _ = sender.send(findings);
+            ctx.request_repaint();
+        });
+    }
+
     pub fn process_updates(&mut self) {
         let chunks_done = if let Some(receiver) = &self.chunk_receiver {
             loop {
                 match receiver.try_recv() {
                     Ok(chunks) => {
-                        self.chunks.extend(chunks);
+                        Arc::make_mut(&mut self.chunks).extend(chunks);
                     }
                     Err(TryRecvError::Empty) => {
                         break false;
@@ -376,6 +522,10 @@ impl FftAxis {
         if chunks_done {
             self.chunk_receiver = None;
             self.redraw_textures();
+
+            if self.identify_noise {
+                self.identify_noise_sources();
+            }
         }
 
         if let Some(receiver) = &self.time_texture_receiver {
@@ -391,6 +541,11 @@ impl FftAxis {
         }
     }
 
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.ranges = ranges;
+        self.recalculate_ffts();
+    }
+
     pub fn set_fft_settings(&mut self, fft_settings: FftSettings) {
         let old_fft_settings = self.fft_settings.clone();
         self.fft_settings = fft_settings;
@@ -402,7 +557,12 @@ impl FftAxis {
         }
     }
 
-    pub fn show_time(&mut self, ui: &mut egui::Ui, total_width: f32) -> egui::Response {
+    pub fn show_time(
+        &mut self,
+        ui: &mut egui::Ui,
+        total_width: f32,
+        motor_frequencies: Option<&MotorFrequencies>,
+    ) -> egui::Response {
         let max_freq = self.flight_data.sample_rate() / 2.0;
         let height = if ui.available_width() < total_width {
             ui.available_height()
@@ -438,11 +598,25 @@ impl FftAxis {
 
                     plot_ui.image(plot_image);
                 }
+
+                if let Some(motor_frequencies) = motor_frequencies {
+                    MotorFrequencies::plot_harmonics(
+                        plot_ui,
+                        &motor_frequencies.time,
+                        &self.fft_settings.rpm_harmonics,
+                        max_freq,
+                    );
+                }
             })
             .response
     }
 
-    pub fn show_throttle(&mut self, ui: &mut egui::Ui, total_width: f32) -> egui::Response {
+    pub fn show_throttle(
+        &mut self,
+        ui: &mut egui::Ui,
+        total_width: f32,
+        motor_frequencies: Option<&MotorFrequencies>,
+    ) -> egui::Response {
         let max_freq = self.flight_data.sample_rate() / 2.0;
         let height = if ui.available_width() < total_width {
             ui.available_height()
@@ -480,6 +654,15 @@ impl FftAxis {
 
                     plot_ui.image(plot_image);
                 }
+
+                if let Some(motor_frequencies) = motor_frequencies {
+                    MotorFrequencies::plot_harmonics(
+                        plot_ui,
+                        &motor_frequencies.throttle,
+                        &self.fft_settings.rpm_harmonics,
+                        max_freq,
+                    );
+                }
             })
             .response
     }
@@ -489,6 +672,7 @@ impl FftAxis {
         ui: &mut egui::Ui,
         domain: VibeDomain,
         total_width: f32,
+        motor_frequencies: Option<&MotorFrequencies>,
     ) -> egui::Response {
         self.process_updates();
 
@@ -496,8 +680,9 @@ impl FftAxis {
             ui.label("")
         } else {
             match domain {
-                VibeDomain::Time => self.show_time(ui, total_width),
-                VibeDomain::Throttle => self.show_throttle(ui, total_width),
+                VibeDomain::Time => self.show_time(ui, total_width, motor_frequencies),
+                VibeDomain::Throttle => self.show_throttle(ui, total_width, motor_frequencies),
+                VibeDomain::Frequency => ui.label(""),
             }
         }
     }
@@ -512,17 +697,60 @@ impl FftVectorSeries {
         ctx: &egui::Context,
         fft_settings: FftSettings,
         fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
         value_callback: fn(&FlightData) -> [Option<&Vec<f32>>; 3],
     ) -> Self {
+        let value_callback: FftAxisValueCallback = Arc::new(value_callback);
         let axes = [
-            FftAxis::new(ctx, fft_settings.clone(), 0, fd.clone(), value_callback),
-            FftAxis::new(ctx, fft_settings.clone(), 1, fd.clone(), value_callback),
-            FftAxis::new(ctx, fft_settings.clone(), 2, fd, value_callback),
+            FftAxis::new(
+                ctx,
+                fft_settings.clone(),
+                0,
+                fd.clone(),
+                ranges.clone(),
+                value_callback.clone(),
+            ),
+            FftAxis::new(
+                ctx,
+                fft_settings.clone(),
+                1,
+                fd.clone(),
+                ranges.clone(),
+                value_callback.clone(),
+            ),
+            FftAxis::new(ctx, fft_settings.clone(), 2, fd, ranges, value_callback),
         ];
 
         Self { axes }
     }
 
+    pub fn with_noise_identification(mut self) -> Self {
+        for axis in self.axes.iter_mut() {
+            axis.identify_noise = true;
+        }
+        self
+    }
+
+    /// Noise sources found in all axes, ranked by severity. `None` while the spectrograms
+    /// are still being calculated.
+    pub fn noise_findings(&mut self) -> Option<Vec<(usize, NoiseFinding)>> {
+        let mut findings = Vec::new();
+        for (i, axis) in self.axes.iter_mut().enumerate() {
+            axis.process_updates();
+            let axis_findings = axis.noise_findings.get().as_ref()?;
+            findings.extend(axis_findings.iter().cloned().map(|f| (i, f)));
+        }
+
+        findings.sort_by(|(_, a), (_, b)| b.severity.total_cmp(&a.severity));
+        Some(findings)
+    }
+
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        for axis in self.axes.iter_mut() {
+            axis.set_ranges(ranges.clone());
+        }
+    }
+
     pub fn set_fft_settings(&mut self, fft_settings: FftSettings) {
         self.axes[0].set_fft_settings(fft_settings.clone());
         self.axes[1].set_fft_settings(fft_settings.clone());
@@ -534,12 +762,13 @@ impl FftVectorSeries {
         ui: &mut egui::Ui,
         domain: VibeDomain,
         total_width: f32,
+        motor_frequencies: Option<&MotorFrequencies>,
     ) -> egui::Response {
         ui.vertical(|ui| {
             for (i, axis) in self.axes.iter_mut().enumerate() {
                 ui.vertical(|ui| {
                     ui.set_height(ui.available_height() / (3 - i) as f32);
-                    axis.show(ui, domain, total_width);
+                    axis.show(ui, domain, total_width, motor_frequencies);
                 });
             }
         })
@@ -554,21 +783,49 @@ pub struct VibeTab {
     gyro_filtered_enabled: bool,
     dterm_raw_enabled: bool,
     dterm_filtered_enabled: bool,
+    accel_enabled: bool,
 
     fft_settings: FftSettings,
 
     gyro_raw_ffts: FftVectorSeries,
     gyro_filtered_ffts: FftVectorSeries,
+    dterm_raw_ffts: FftVectorSeries,
     dterm_filtered_ffts: FftVectorSeries,
+    /// Only calculated once the accelerometer is enabled, since it's off by default
+    accel_ffts: Option<FftVectorSeries>,
+    /// Spectrogram of any field of the flight, including derived series
+    custom_field: Option<String>,
+    custom_ffts: Option<FftAxis>,
+
+    fd: Arc<FlightData>,
+    /// Sample ranges of the selected flight phases
+    ranges: Vec<Range<usize>>,
+    field_names: Vec<String>,
+    psd_view: PsdView,
+    noise_summary: NoiseSummary,
+    accel_vibration: AccelVibration,
+    motor_frequencies: Option<MotorFrequencies>,
+
+    /// Whether noise sources are identified from the raw gyro, or from the filtered one if
+    /// the log doesn't contain the raw gyro.
+    noise_from_gyro_raw: bool,
 }
 
 impl VibeTab {
-    pub fn new(ctx: &egui::Context, fd: Arc<FlightData>) -> Self {
+    pub fn new(ctx: &egui::Context, fd: Arc<FlightData>, ranges: Vec<Range<usize>>) -> Self {
         let fft_settings = FftSettings::default();
+        let gyro_raw_available = fd
+            .gyro_unfiltered()
+            .map(|v| !v[0].is_empty())
+            .unwrap_or(false);
 
         // TODO: unwrap
-        let gyro_raw_ffts =
-            FftVectorSeries::new(ctx, fft_settings.clone(), fd.clone(), |fd: &FlightData| {
+        let gyro_raw_ffts = FftVectorSeries::new(
+            ctx,
+            fft_settings.clone(),
+            fd.clone(),
+            ranges.clone(),
+            |fd: &FlightData| {
                 fd.gyro_unfiltered()
                     .unwrap()
                     .into_iter()
@@ -576,9 +833,14 @@ impl VibeTab {
                     .collect::<Vec<_>>()
                     .try_into()
                     .unwrap()
-            });
-        let gyro_filtered_ffts =
-            FftVectorSeries::new(ctx, fft_settings.clone(), fd.clone(), |fd: &FlightData| {
+            },
+        );
+        let gyro_filtered_ffts = FftVectorSeries::new(
+            ctx,
+            fft_settings.clone(),
+            fd.clone(),
+            ranges.clone(),
+            |fd: &FlightData| {
                 fd.gyro_filtered()
                     .unwrap()
                     .into_iter()
@@ -586,46 +848,198 @@ impl VibeTab {
                     .collect::<Vec<_>>()
                     .try_into()
                     .unwrap()
-            });
-        let dterm_filtered_ffts =
-            FftVectorSeries::new(ctx, fft_settings.clone(), fd.clone(), |fd: &FlightData| {
-                fd.d()
-            });
+            },
+        );
+        let (gyro_raw_ffts, gyro_filtered_ffts) = if gyro_raw_available {
+            (
+                gyro_raw_ffts.with_noise_identification(),
+                gyro_filtered_ffts,
+            )
+        } else {
+            (
+                gyro_raw_ffts,
+                gyro_filtered_ffts.with_noise_identification(),
+            )
+        };
+        let dterm_raw_ffts = FftVectorSeries::new(
+            ctx,
+            fft_settings.clone(),
+            fd.clone(),
+            ranges.clone(),
+            |fd: &FlightData| fd.d_unfiltered(),
+        );
+        let dterm_filtered_ffts = FftVectorSeries::new(
+            ctx,
+            fft_settings.clone(),
+            fd.clone(),
+            ranges.clone(),
+            |fd: &FlightData| fd.d(),
+        );
 
         Self {
             domain: VibeDomain::Time,
 
-            gyro_raw_enabled: fd
-                .gyro_unfiltered()
-                .map(|v| !v[0].is_empty())
-                .unwrap_or(false),
+            gyro_raw_enabled: gyro_raw_available,
             gyro_filtered_enabled: true,
-            dterm_raw_enabled: false, // TODO
+            dterm_raw_enabled: fd.d_unfiltered().iter().any(|d| d.is_some()),
             dterm_filtered_enabled: true,
+            accel_enabled: false,
 
             fft_settings,
 
             gyro_raw_ffts,
             gyro_filtered_ffts,
-            //dterm_raw_ffts,
+            dterm_raw_ffts,
             dterm_filtered_ffts,
+            accel_ffts: None,
+            custom_field: None,
+            custom_ffts: None,
+
+            field_names: fd.main_values.keys().cloned().sorted().collect(),
+            fd: fd.clone(),
+            motor_frequencies: MotorFrequencies::new(&fd, &ranges),
+            noise_summary: NoiseSummary::new(ctx, fd.clone(), ranges.clone()),
+            accel_vibration: AccelVibration::new(ctx, fd.clone(), ranges.clone()),
+            psd_view: PsdView::new(ctx, fd, ranges.clone()),
+            ranges,
+
+            noise_from_gyro_raw: gyro_raw_available,
         }
     }
 
+    /// Restricts all spectrograms and summaries to other `ranges`, keeping the settings and the
+    /// selected series.
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        for ffts in [
+            &mut self.gyro_raw_ffts,
+            &mut self.gyro_filtered_ffts,
+            &mut self.dterm_raw_ffts,
+            &mut self.dterm_filtered_ffts,
+        ] {
+            ffts.set_ranges(ranges.clone());
+        }
+        if let Some(accel_ffts) = self.accel_ffts.as_mut() {
+            accel_ffts.set_ranges(ranges.clone());
+        }
+        if let Some(custom_ffts) = self.custom_ffts.as_mut() {
+            custom_ffts.set_ranges(ranges.clone());
+        }
+
+        self.motor_frequencies = MotorFrequencies::new(&self.fd, &ranges);
+        self.noise_summary.set_ranges(ranges.clone());
+        self.accel_vibration.set_ranges(ranges.clone());
+        self.psd_view.set_ranges(ranges.clone());
+        self.ranges = ranges;
+    }
+
     pub fn update_fft_settings(&mut self) {
         self.gyro_raw_ffts
             .set_fft_settings(self.fft_settings.clone());
         self.gyro_filtered_ffts
             .set_fft_settings(self.fft_settings.clone());
-        //self.dterm_raw_ffts.set_fft_settings(self.fft_settings.clone());
+        self.dterm_raw_ffts
+            .set_fft_settings(self.fft_settings.clone());
         self.dterm_filtered_ffts
             .set_fft_settings(self.fft_settings.clone());
+        if let Some(accel_ffts) = self.accel_ffts.as_mut() {
+            accel_ffts.set_fft_settings(self.fft_settings.clone());
+        }
+        if let Some(custom_ffts) = self.custom_ffts.as_mut() {
+            custom_ffts.set_fft_settings(self.fft_settings.clone());
+        }
+    }
+
+    fn enable_accel_ffts(&mut self, ctx: &egui::Context) {
+        if self.accel_ffts.is_none() {
+            self.accel_ffts = Some(FftVectorSeries::new(
+                ctx,
+                self.fft_settings.clone(),
+                self.fd.clone(),
+                self.ranges.clone(),
+                |fd: &FlightData| fd.accel().map(|a| a.map(Some)).unwrap_or([None; 3]),
+            ));
+        }
+    }
+
+    fn set_custom_field(&mut self, ctx: &egui::Context, field: Option<String>) {
+        self.custom_ffts = field.clone().map(|name| {
+            let value_callback: FftAxisValueCallback =
+                Arc::new(move |fd: &FlightData| [fd.main_values.get(&name), None, None]);
+            FftAxis::new(
+                ctx,
+                self.fft_settings.clone(),
+                0,
+                self.fd.clone(),
+                self.ranges.clone(),
+                value_callback,
+            )
+        });
+        self.custom_field = field;
+    }
+
+    /// Swaps in a new version of the flight, e.g. with derived series added. Only the custom
+    /// field can show those, so it is the only spectrogram that may need recalculating.
+    pub fn set_flight_data(&mut self, ctx: &egui::Context, fd: Arc<FlightData>) {
+        let custom_field = self
+            .custom_field
+            .clone()
+            .filter(|name| fd.main_values.contains_key(name));
+        let custom_changed = custom_field != self.custom_field
+            || custom_field
+                .as_ref()
+                .is_some_and(|name| self.fd.main_values.get(name) != fd.main_values.get(name));
+
+        self.field_names = fd.main_values.keys().cloned().sorted().collect();
+        self.fd = fd;
+        if custom_changed {
+            self.set_custom_field(ctx, custom_field);
+        }
+    }
+
+    fn show_noise_findings(ui: &mut egui::Ui, findings: Option<&[(usize, NoiseFinding)]>) {
+        egui::CollapsingHeader::new("🔍 Noise Sources").show(ui, |ui| match findings {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Analyzing spectrograms...");
+                });
+            }
+            Some([]) => {
+                ui.label("No distinct noise sources found.");
+            }
+            Some(findings) => {
+                egui::Grid::new("noise_findings")
+                    .num_columns(6)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in ["#", "Source", "Axis", "Frequency", "Worst at", "Severity"] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+
+                        let listed = findings.iter().take(MAX_LISTED_NOISE_FINDINGS);
+                        for (rank, (axis, finding)) in listed.enumerate() {
+                            let (low, high) = finding.frequency_range;
+                            ui.label(format!("{}", rank + 1));
+                            ui.label(finding.source.to_string());
+                            ui.label(AXIS_LABELS[*axis]);
+                            ui.label(format!("{:.0}–{:.0}Hz", low.max(0.0), high));
+                            ui.label(format!("{:.0}% throttle", finding.worst_throttle / 10.0));
+                            ui.label(format!("+{:.1}dB", finding.severity));
+                            ui.end_row();
+                        }
+                    });
+            }
+        });
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui) {
         let old_fft_settings = self.fft_settings.clone();
         let fft_size = self.fft_settings.size;
         let total_width = ui.available_width();
+        let spectrogram = self.domain != VibeDomain::Frequency;
+        let rpm_available = self.motor_frequencies.is_some();
+        let mut custom_field = self.custom_field.clone();
 
         FlexLayout::new(1500.0, "Settings")
             .add(|ui| {
@@ -633,6 +1047,7 @@ impl VibeTab {
                     ui.label("Domain:");
                     ui.selectable_value(&mut self.domain, VibeDomain::Time, "🕙 Time");
                     ui.selectable_value(&mut self.domain, VibeDomain::Throttle, "🏃 Throttle");
+                    ui.selectable_value(&mut self.domain, VibeDomain::Frequency, "📉 Frequency");
                 })
                 .response
             })
@@ -643,6 +1058,22 @@ impl VibeTab {
                     ui.toggle_value(&mut self.gyro_filtered_enabled, "Gyro (filtered)");
                     ui.toggle_value(&mut self.dterm_raw_enabled, "D term (raw)");
                     ui.toggle_value(&mut self.dterm_filtered_enabled, "D term (filtered)");
+                    ui.add_enabled_ui(spectrogram, |ui| {
+                        ui.toggle_value(&mut self.accel_enabled, "Accelerometer");
+                        egui::ComboBox::from_id_source("vibe_custom_field")
+                            .selected_text(custom_field.as_deref().unwrap_or("Other field"))
+                            .height(400.0)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut custom_field, None, "None");
+                                for name in self.field_names.iter() {
+                                    ui.selectable_value(
+                                        &mut custom_field,
+                                        Some(name.clone()),
+                                        name,
+                                    );
+                                }
+                            });
+                    });
                 })
                 .response
             })
@@ -659,7 +1090,7 @@ impl VibeTab {
                 })
                 .response
             })
-            .add(|ui| {
+            .add_enabled(spectrogram, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("FFT Step Size:");
                     for value in &[1, 8, 32, 128, 256, 512, 1024] {
@@ -674,7 +1105,7 @@ impl VibeTab {
                 })
                 .response
             })
-            .add(|ui| {
+            .add_enabled(spectrogram, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Colorscheme:");
                     for value in &[
@@ -691,7 +1122,7 @@ impl VibeTab {
                 })
                 .response
             })
-            .add(|ui| {
+            .add_enabled(spectrogram, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("FFTMax:");
                     ui.add(
@@ -702,32 +1133,124 @@ impl VibeTab {
                 })
                 .response
             })
+            .add_enabled(spectrogram && rpm_available, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("RPM Harmonics:");
+                    for (enabled, label) in self
+                        .fft_settings
+                        .rpm_harmonics
+                        .iter_mut()
+                        .zip(["1st", "2nd", "3rd"])
+                    {
+                        ui.toggle_value(enabled, label);
+                    }
+                })
+                .response
+            })
+            .add_enabled(!spectrogram, |ui| self.psd_view.show_settings(ui))
             .show(ui);
 
+        if custom_field != self.custom_field {
+            self.set_custom_field(ui.ctx(), custom_field);
+        }
+        if self.accel_enabled {
+            self.enable_accel_ffts(ui.ctx());
+        }
+
         if self.fft_settings != old_fft_settings {
             self.fft_settings.step_size =
                 usize::min(self.fft_settings.step_size, self.fft_settings.size);
             self.update_fft_settings();
         }
 
+        let noise_findings = if self.noise_from_gyro_raw {
+            self.gyro_raw_ffts.noise_findings()
+        } else {
+            self.gyro_filtered_ffts.noise_findings()
+        };
+        Self::show_noise_findings(ui, noise_findings.as_deref());
+        self.noise_summary.show(ui);
+        self.accel_vibration.show(ui);
+
         ui.separator();
 
+        if self.domain == VibeDomain::Frequency {
+            let enabled_series: Vec<_> = [
+                (self.gyro_raw_enabled, PsdSeries::GyroRaw),
+                (self.gyro_filtered_enabled, PsdSeries::GyroFiltered),
+                (self.dterm_raw_enabled, PsdSeries::DtermRaw),
+                (self.dterm_filtered_enabled, PsdSeries::DtermFiltered),
+            ]
+            .into_iter()
+            .filter_map(|(enabled, series)| enabled.then_some(series))
+            .collect();
+            self.psd_view.show(ui, &enabled_series, fft_size);
+            return;
+        }
+
         FlexColumns::new(MIN_WIDE_WIDTH)
             .column_enabled(self.gyro_raw_enabled, |ui| {
                 ui.heading("Gyro (raw)");
-                self.gyro_raw_ffts.show(ui, self.domain, total_width)
+                self.gyro_raw_ffts.show(
+                    ui,
+                    self.domain,
+                    total_width,
+                    self.motor_frequencies.as_ref(),
+                )
             })
             .column_enabled(self.gyro_filtered_enabled, |ui| {
                 ui.heading("Gyro (filtered)");
-                self.gyro_filtered_ffts.show(ui, self.domain, total_width)
+                self.gyro_filtered_ffts.show(
+                    ui,
+                    self.domain,
+                    total_width,
+                    self.motor_frequencies.as_ref(),
+                )
             })
             .column_enabled(self.dterm_raw_enabled, |ui| {
-                ui.heading("D Term (raw)")
-                //self.dterm_raw_ffts.show(ui, self.domain, total_width)
+                ui.heading("D Term (raw)");
+                self.dterm_raw_ffts.show(
+                    ui,
+                    self.domain,
+                    total_width,
+                    self.motor_frequencies.as_ref(),
+                )
             })
             .column_enabled(self.dterm_filtered_enabled, |ui| {
                 ui.heading("D Term (filtered)");
-                self.dterm_filtered_ffts.show(ui, self.domain, total_width)
+                self.dterm_filtered_ffts.show(
+                    ui,
+                    self.domain,
+                    total_width,
+                    self.motor_frequencies.as_ref(),
+                )
+            })
+            .column_enabled(self.accel_enabled, |ui| {
+                ui.heading("Accelerometer");
+                match self.accel_ffts.as_mut() {
+                    Some(accel_ffts) => accel_ffts.show(
+                        ui,
+                        self.domain,
+                        total_width,
+                        self.motor_frequencies.as_ref(),
+                    ),
+                    None => ui.spinner(),
+                }
+            })
+            .column_enabled(self.custom_ffts.is_some(), |ui| {
+                ui.heading(self.custom_field.as_deref().unwrap_or_default());
+                ui.vertical(|ui| {
+                    ui.set_height(ui.available_height() / 3.0);
+                    if let Some(custom_ffts) = self.custom_ffts.as_mut() {
+                        custom_ffts.show(
+                            ui,
+                            self.domain,
+                            total_width,
+                            self.motor_frequencies.as_ref(),
+                        );
+                    }
+                })
+                .response
             })
             .show(ui);
     }
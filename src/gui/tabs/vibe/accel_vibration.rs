@@ -0,0 +1,97 @@
+use std::ops::Range;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use egui_plot::{Corner, Legend, PlotPoints};
+
+use crate::flight_data::FlightData;
+use crate::gui::colors::Colors;
+use crate::utils::{execute_in_background, BackgroundCompStore};
+use crate::vibration::{vibration_level, VIBRATION_BAND};
+
+use super::PLOT_HEIGHT;
+
+/// Vibration level measured by the accelerometer over time
+pub struct AccelVibration {
+    ctx: egui::Context,
+    fd: Arc<FlightData>,
+    level: Option<BackgroundCompStore<Vec<(f64, f64)>>>,
+}
+
+impl AccelVibration {
+    pub fn new(ctx: &egui::Context, fd: Arc<FlightData>, ranges: Vec<Range<usize>>) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            level: Self::calculate(ctx, fd.clone(), ranges),
+            fd,
+        }
+    }
+
+    /// Recalculates the vibration level for other `ranges`.
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.level = Self::calculate(&self.ctx, self.fd.clone(), ranges);
+    }
+
+    fn calculate(
+        ctx: &egui::Context,
+        fd: Arc<FlightData>,
+        ranges: Vec<Range<usize>>,
+    ) -> Option<BackgroundCompStore<Vec<(f64, f64)>>> {
+        fd.accel()?;
+
+        let (sender, receiver) = channel();
+        let ctx = ctx.clone();
+        execute_in_background(async move {
+            let accel = fd.accel().unwrap();
+            let level =
+                vibration_level(&fd.times, accel.map(|a| &a[..]), fd.sample_rate(), &ranges);
+            let _ = sender.send(level);
+            ctx.request_repaint();
+        });
+        Some(BackgroundCompStore::new(receiver))
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let Some(level) = self.level.as_mut() else {
+            return;
+        };
+
+        let title = format!(
+            "📳 Accelerometer Vibration ({:.0}–{:.0}Hz)",
+            VIBRATION_BAND.0, VIBRATION_BAND.1
+        );
+        egui::CollapsingHeader::new(title).show(ui, |ui| {
+            let Some(level) = level.get() else {
+                ui.spinner();
+                return;
+            };
+
+            let mean = level.iter().map(|(_, g)| g).sum::<f64>() / level.len().max(1) as f64;
+            let max = level.iter().fold(0.0f64, |max, (_, g)| max.max(*g));
+            ui.horizontal(|ui| {
+                ui.label("Mean:");
+                ui.strong(format!("{:.2}g", mean));
+                ui.label("Max:");
+                ui.strong(format!("{:.2}g", max));
+            });
+
+            let color = Colors::get(ui).vibration;
+            egui_plot::Plot::new(ui.next_auto_id())
+                .legend(Legend::default().position(Corner::LeftTop))
+                .show_grid(true)
+                .y_axis_position(egui_plot::HPlacement::Right)
+                .y_axis_width(3)
+                .y_axis_formatter(|mark, _, _| format!("{:.2}g", mark.value))
+                .x_axis_formatter(|mark, _, _| format!("{}s", mark.value))
+                .height(PLOT_HEIGHT)
+                .show(ui, |plot_ui| {
+                    let points: PlotPoints = level.iter().map(|(t, g)| [*t, *g]).collect();
+                    plot_ui.line(
+                        egui_plot::Line::new(points)
+                            .name("Vibration (RMS)")
+                            .color(color),
+                    );
+                });
+        });
+    }
+}
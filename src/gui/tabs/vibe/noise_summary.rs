@@ -0,0 +1,282 @@
+use std::ops::Range;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use egui::DragValue;
+
+use crate::flight_data::FlightData;
+use crate::gui::export::csv_export_buttons;
+use crate::spectrum::welch_psd;
+use crate::utils::{execute_in_background, BackgroundCompStore};
+
+use super::psd::PsdSeries;
+use super::AXIS_LABELS;
+
+const SEGMENT_SIZE: usize = 1024;
+const DEFAULT_BAND_EDGES: [f64; 3] = [50.0, 150.0, 300.0];
+
+/// Pairs of series for which the attenuation by the filters is shown
+const ATTENUATION_PAIRS: [(&str, PsdSeries, PsdSeries); 2] = [
+    ("Gyro", PsdSeries::GyroRaw, PsdSeries::GyroFiltered),
+    ("D term", PsdSeries::DtermRaw, PsdSeries::DtermFiltered),
+];
+
+/// Per axis and band, `None` for a band without any frequency bins
+type AxisBandValues = [Option<Vec<Option<f64>>>; 3];
+
+struct NoiseMetrics {
+    bands: Vec<(f64, f64)>,
+    /// RMS per series, axis and band
+    rms: Vec<(PsdSeries, AxisBandValues)>,
+    /// Attenuation from raw to filtered in dB, per pair, axis and band
+    attenuation: Vec<(&'static str, AxisBandValues)>,
+}
+
+impl NoiseMetrics {
+    fn calculate(fd: &FlightData, ranges: &[Range<usize>], bands: Vec<(f64, f64)>) -> Self {
+        let sample_rate = fd.sample_rate();
+
+        let rms: Vec<(PsdSeries, AxisBandValues)> = PsdSeries::ALL
+            .iter()
+            .map(|series| {
+                let values = series.values(fd).map(|values| {
+                    let spectrum = welch_psd(values?, ranges, sample_rate, SEGMENT_SIZE)?;
+                    Some(
+                        bands
+                            .iter()
+                            .map(|(low, high)| spectrum.band_rms(*low, *high))
+                            .collect(),
+                    )
+                });
+                (*series, values)
+            })
+            .collect();
+
+        let series_rms = |series: PsdSeries, axis: usize| {
+            rms.iter()
+                .find(|(s, _)| *s == series)
+                .and_then(|(_, values)| values[axis].as_ref())
+        };
+        let attenuation = ATTENUATION_PAIRS
+            .iter()
+            .map(|(name, raw, filtered)| {
+                let values = [0, 1, 2].map(|axis| {
+                    let raw = series_rms(*raw, axis)?;
+                    let filtered = series_rms(*filtered, axis)?;
+                    Some(
+                        raw.iter()
+                            .zip(filtered.iter())
+                            .map(|(r, f)| match (r, f) {
+                                (Some(r), Some(f)) if *f > 0.0 => Some(20.0 * (r / f).log10()),
+                                _ => None,
+                            })
+                            .collect(),
+                    )
+                });
+                (*name, values)
+            })
+            .collect();
+
+        Self {
+            bands,
+            rms,
+            attenuation,
+        }
+    }
+
+    fn band_label(band: &(f64, f64)) -> String {
+        if band.1.is_finite() {
+            format!("{:.0}–{:.0}Hz", band.0, band.1)
+        } else {
+            format!("{:.0}Hz+", band.0)
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = (String, usize, &Vec<Option<f64>>)> {
+        let rms = self.rms.iter().flat_map(|(series, values)| {
+            values.iter().enumerate().filter_map(move |(axis, v)| {
+                v.as_ref()
+                    .map(|v| (format!("{} RMS", series.name()), axis, v))
+            })
+        });
+        let attenuation = self.attenuation.iter().flat_map(|(name, values)| {
+            values.iter().enumerate().filter_map(move |(axis, v)| {
+                v.as_ref()
+                    .map(|v| (format!("{} attenuation (dB)", name), axis, v))
+            })
+        });
+
+        rms.chain(attenuation)
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("metric,axis");
+        for band in self.bands.iter() {
+            csv += &format!(",{}", Self::band_label(band));
+        }
+        csv += "\n";
+
+        for (name, axis, values) in self.rows() {
+            csv += &format!("{},{}", name, AXIS_LABELS[axis]);
+            for value in values {
+                csv += ",";
+                if let Some(value) = value {
+                    csv += &value.to_string();
+                }
+            }
+            csv += "\n";
+        }
+
+        csv
+    }
+}
+
+/// RMS noise of all series in configurable frequency bands, plus the attenuation between the
+/// raw and filtered series.
+pub struct NoiseSummary {
+    ctx: egui::Context,
+    fd: Arc<FlightData>,
+    ranges: Vec<Range<usize>>,
+
+    band_edges: Vec<f64>,
+
+    metrics: BackgroundCompStore<NoiseMetrics>,
+    calculated_for: Option<Vec<f64>>,
+}
+
+impl NoiseSummary {
+    pub fn new(ctx: &egui::Context, fd: Arc<FlightData>, ranges: Vec<Range<usize>>) -> Self {
+        let max_freq = fd.sample_rate() / 2.0;
+        Self {
+            ctx: ctx.clone(),
+            fd,
+            ranges,
+
+            band_edges: DEFAULT_BAND_EDGES
+                .into_iter()
+                .filter(|edge| *edge < max_freq)
+                .collect(),
+
+            metrics: BackgroundCompStore::new(channel().1),
+            calculated_for: None,
+        }
+    }
+
+    /// Restricts the metrics to other `ranges`, recalculating them when shown next.
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.ranges = ranges;
+        self.calculated_for = None;
+    }
+
+    fn bands(&self) -> Vec<(f64, f64)> {
+        std::iter::once(0.0)
+            .chain(self.band_edges.iter().copied())
+            .zip(
+                self.band_edges
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(f64::INFINITY)),
+            )
+            .collect()
+    }
+
+    fn recalculate(&mut self) {
+        let (sender, receiver) = channel();
+        self.metrics = BackgroundCompStore::new(receiver);
+
+        let fd = self.fd.clone();
+        let ranges = self.ranges.clone();
+        let bands = self.bands();
+        let ctx = self.ctx.clone();
+        execute_in_background(async move {
+            let _ = sender.send(NoiseMetrics::calculate(&fd, &ranges, bands));
+            ctx.request_repaint();
+        });
+    }
+
+    fn show_band_settings(&mut self, ui: &mut egui::Ui) {
+        let max_freq = self.fd.sample_rate() / 2.0;
+
+        ui.horizontal(|ui| {
+            ui.label("Bands:");
+            ui.label("0");
+            for i in 0..self.band_edges.len() {
+                let min = if i == 0 {
+                    1.0
+                } else {
+                    self.band_edges[i - 1] + 1.0
+                };
+                let max = self
+                    .band_edges
+                    .get(i + 1)
+                    .map(|e| e - 1.0)
+                    .unwrap_or(max_freq)
+                    .max(min);
+
+                ui.label("–");
+                ui.add(
+                    DragValue::new(&mut self.band_edges[i])
+                        .clamp_range(min..=max)
+                        .speed(1.0)
+                        .suffix("Hz"),
+                );
+            }
+            ui.label("– ∞");
+
+            let last = self.band_edges.last().copied().unwrap_or(0.0);
+            if ui.button("➕").clicked() && last + 1.0 < max_freq {
+                self.band_edges
+                    .push(f64::min(last * 2.0, max_freq).max(last + 1.0));
+            }
+            if self.band_edges.len() > 1 && ui.button("➖").clicked() {
+                self.band_edges.pop();
+            }
+        });
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("📊 Noise Summary").show(ui, |ui| {
+            self.show_band_settings(ui);
+
+            let dragging = ui.ctx().input(|i| i.pointer.any_down());
+            if self.calculated_for.as_ref() != Some(&self.band_edges) && !dragging {
+                self.recalculate();
+                self.calculated_for = Some(self.band_edges.clone());
+            }
+
+            let Some(metrics) = self.metrics.get() else {
+                ui.spinner();
+                return;
+            };
+
+            egui::Grid::new("noise_summary")
+                .num_columns(metrics.bands.len() + 2)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Metric");
+                    ui.strong("Axis");
+                    for band in metrics.bands.iter() {
+                        ui.strong(NoiseMetrics::band_label(band));
+                    }
+                    ui.end_row();
+
+                    for (name, axis, values) in metrics.rows() {
+                        ui.label(name);
+                        ui.label(AXIS_LABELS[axis]);
+                        for value in values {
+                            match value {
+                                Some(value) => ui.monospace(format!("{:.2}", value)),
+                                None => ui.label(""),
+                            };
+                        }
+                        ui.end_row();
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                let file_name = format!("noise_summary_{}.csv", self.fd.index + 1);
+                csv_export_buttons(ui, &file_name, || metrics.to_csv());
+            });
+        });
+    }
+}
@@ -0,0 +1,251 @@
+use std::ops::Range;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+
+use egui::{Align2, Color32, DragValue};
+use egui_plot::{Corner, Legend, Line, PlotPoint, PlotPoints, Points, Text};
+
+use crate::flight_data::FlightData;
+use crate::gui::colors::Colors;
+use crate::spectrum::welch_psd;
+use crate::utils::{execute_in_background, BackgroundCompStore};
+
+use super::AXIS_LABELS;
+
+const MAX_LABELED_PEAKS: usize = 3;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum PsdSeries {
+    GyroRaw,
+    GyroFiltered,
+    DtermRaw,
+    DtermFiltered,
+}
+
+impl PsdSeries {
+    pub(super) const ALL: [Self; 4] = [
+        Self::GyroRaw,
+        Self::GyroFiltered,
+        Self::DtermRaw,
+        Self::DtermFiltered,
+    ];
+
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Self::GyroRaw => "Gyro (raw)",
+            Self::GyroFiltered => "Gyro (filtered)",
+            Self::DtermRaw => "D term (raw)",
+            Self::DtermFiltered => "D term (filtered)",
+        }
+    }
+
+    fn color(&self, colors: &Colors) -> Color32 {
+        match self {
+            Self::GyroRaw => colors.gyro_unfiltered,
+            Self::GyroFiltered => colors.gyro_filtered,
+            Self::DtermRaw => colors.d_unfiltered,
+            Self::DtermFiltered => colors.d,
+        }
+    }
+
+    pub(super) fn values<'a>(&self, fd: &'a FlightData) -> [Option<&'a Vec<f32>>; 3] {
+        match self {
+            Self::GyroRaw => fd
+                .gyro_unfiltered()
+                .map(|g| g.map(Some))
+                .unwrap_or_default(),
+            Self::GyroFiltered => fd.gyro_filtered().map(|g| g.map(Some)).unwrap_or_default(),
+            Self::DtermRaw => fd.d_unfiltered(),
+            Self::DtermFiltered => fd.d(),
+        }
+    }
+}
+
+struct PsdCurve {
+    series: PsdSeries,
+    decibels: Vec<(f64, f64)>,
+    peaks: Vec<(f64, f64)>,
+}
+
+/// Welch-averaged power spectra of all series over a selectable time range, one plot per axis.
+pub struct PsdView {
+    ctx: egui::Context,
+    fd: Arc<FlightData>,
+    ranges: Vec<Range<usize>>,
+
+    range: (f64, f64),
+    log_frequency: bool,
+
+    curves: BackgroundCompStore<[Vec<PsdCurve>; 3]>,
+    calculated_for: Option<((f64, f64), usize)>,
+}
+
+impl PsdView {
+    pub fn new(ctx: &egui::Context, fd: Arc<FlightData>, ranges: Vec<Range<usize>>) -> Self {
+        let range = (
+            fd.times.first().copied().unwrap_or_default(),
+            fd.times.last().copied().unwrap_or_default(),
+        );
+
+        Self {
+            ctx: ctx.clone(),
+            fd,
+            ranges,
+
+            range,
+            log_frequency: false,
+
+            curves: BackgroundCompStore::new(channel().1),
+            calculated_for: None,
+        }
+    }
+
+    /// Restricts the spectra to other `ranges`, recalculating them when shown next.
+    pub fn set_ranges(&mut self, ranges: Vec<Range<usize>>) {
+        self.ranges = ranges;
+        self.calculated_for = None;
+    }
+
+    fn recalculate(&mut self, fft_size: usize) {
+        let (sender, receiver) = channel();
+        self.curves = BackgroundCompStore::new(receiver);
+
+        let fd = self.fd.clone();
+        let ranges = self.ranges.clone();
+        let (start, end) = self.range;
+        let ctx = self.ctx.clone();
+        execute_in_background(async move {
+            let sample_rate = fd.sample_rate();
+            let start = fd.times.partition_point(|t| *t < start);
+            let end = fd.times.partition_point(|t| *t <= end);
+            let ranges: Vec<_> = ranges
+                .iter()
+                .map(|r| r.start.max(start)..r.end.min(end))
+                .filter(|r| !r.is_empty())
+                .collect();
+
+            let curves = [0, 1, 2].map(|axis| {
+                PsdSeries::ALL
+                    .iter()
+                    .filter_map(|series| {
+                        let values = series.values(&fd)[axis]?;
+                        let spectrum = welch_psd(values, &ranges, sample_rate, fft_size)?;
+                        Some(PsdCurve {
+                            series: *series,
+                            decibels: spectrum.decibels(),
+                            peaks: spectrum.peaks(MAX_LABELED_PEAKS),
+                        })
+                    })
+                    .collect()
+            });
+
+            let _ = sender.send(curves);
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn show_settings(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let first = self.fd.times.first().copied().unwrap_or_default();
+        let last = self.fd.times.last().copied().unwrap_or_default();
+        let (start, end) = self.range;
+
+        ui.horizontal(|ui| {
+            ui.label("Range:");
+            ui.add(
+                DragValue::new(&mut self.range.0)
+                    .clamp_range(first..=end)
+                    .speed(0.1)
+                    .suffix("s"),
+            );
+            ui.label("–");
+            ui.add(
+                DragValue::new(&mut self.range.1)
+                    .clamp_range(start..=last)
+                    .speed(0.1)
+                    .suffix("s"),
+            );
+            ui.label("Frequency:");
+            ui.selectable_value(&mut self.log_frequency, false, "Linear");
+            ui.selectable_value(&mut self.log_frequency, true, "Log");
+        })
+        .response
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        enabled_series: &[PsdSeries],
+        fft_size: usize,
+    ) -> egui::Response {
+        // Don't start a new calculation for every frame a value is being dragged.
+        let dragging = ui.ctx().input(|i| i.pointer.any_down());
+        if self.calculated_for != Some((self.range, fft_size)) && !dragging {
+            self.recalculate(fft_size);
+            self.calculated_for = Some((self.range, fft_size));
+        }
+
+        let log_frequency = self.log_frequency;
+        let to_x = move |f: f64| if log_frequency { f.log10() } else { f };
+        let to_hz = move |x: f64| if log_frequency { 10f64.powf(x) } else { x };
+
+        let colors = Colors::get(ui);
+        let Some(curves) = self.curves.get() else {
+            return ui.label("");
+        };
+
+        ui.vertical(|ui| {
+            for (i, axis_curves) in curves.iter().enumerate() {
+                let height = ui.available_height() / (3 - i) as f32;
+
+                egui_plot::Plot::new(ui.next_auto_id())
+                    .legend(Legend::default().position(Corner::RightTop))
+                    .show_grid(true)
+                    .link_axis("psd", true, false)
+                    .link_cursor("psd", true, false)
+                    .y_axis_position(egui_plot::HPlacement::Right)
+                    .y_axis_width(3)
+                    .x_axis_formatter(move |gm, _, _| format!("{:.0}Hz", to_hz(gm.value)))
+                    .y_axis_formatter(|gm, _, _| format!("{:.0}dB", gm.value))
+                    .label_formatter(move |name, val| {
+                        format!("{}\n{:.0}Hz\n{:.1}dB", name, to_hz(val.x), val.y)
+                    })
+                    .height(height)
+                    .show(ui, |plot_ui| {
+                        let visible = axis_curves
+                            .iter()
+                            .filter(|c| enabled_series.contains(&c.series));
+                        for curve in visible {
+                            let name = format!("{} ({})", curve.series.name(), AXIS_LABELS[i]);
+                            let color = curve.series.color(&colors);
+
+                            let points: PlotPoints = curve
+                                .decibels
+                                .iter()
+                                .filter(|(f, _)| !log_frequency || *f > 0.0)
+                                .map(|(f, db)| [to_x(*f), *db])
+                                .collect();
+                            plot_ui.line(Line::new(points).name(&name).color(color));
+
+                            for (f, db) in curve.peaks.iter() {
+                                plot_ui.points(
+                                    Points::new([to_x(*f), *db])
+                                        .name(&name)
+                                        .color(color)
+                                        .radius(3.0),
+                                );
+                                plot_ui.text(
+                                    Text::new(
+                                        PlotPoint::new(to_x(*f), *db),
+                                        format!("{:.0}Hz\n{:.1}dB", f, db),
+                                    )
+                                    .color(color)
+                                    .anchor(Align2::CENTER_BOTTOM),
+                                );
+                            }
+                        }
+                    });
+            }
+        })
+        .response
+    }
+}
@@ -1,84 +1,40 @@
-use std::collections::VecDeque;
+use std::ops::Range;
 
-pub struct OverlappingWindowIter<I>
-where
-    I: Iterator,
-{
-    iterator: I,
-    buffer: VecDeque<I::Item>,
+/// Windows of `window_size` samples within `range`, starting `step` samples apart.
+pub fn windows_in(
+    range: Range<usize>,
     window_size: usize,
-    step_size: usize,
-    filled: bool,
+    step: usize,
+) -> impl Iterator<Item = Range<usize>> {
+    let starts = if window_size == 0 {
+        range.start..range.start
+    } else {
+        range.start..(range.end + 1).saturating_sub(window_size)
+    };
+    starts
+        .step_by(step.max(1))
+        .map(move |start| start..(start + window_size))
 }
 
-impl<I: Iterator> OverlappingWindowIter<I> {
-    pub fn new(iterator: I, window_size: usize, step_size: usize) -> Self {
-        Self {
-            iterator,
-            buffer: VecDeque::new(),
-            window_size,
-            step_size,
-            filled: false,
-        }
-    }
-}
-
-impl<I: Iterator> Iterator for OverlappingWindowIter<I>
-where
-    I::Item: Clone,
-{
-    type Item = Vec<I::Item>; // TODO
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if !self.filled {
-            while self.buffer.len() < self.window_size {
-                if let Some(next) = self.iterator.next() {
-                    self.buffer.push_back(next)
-                } else {
-                    return None;
-                }
-            }
-
-            self.filled = true;
-            return Some(self.buffer.iter().cloned().collect());
-        }
-
-        let mut step = Vec::new();
-        for _ in 0..self.step_size {
-            if let Some(next) = self.iterator.next() {
-                step.push(next);
-            } else {
-                return None;
-            }
-        }
-
-        self.buffer.extend(step);
-        while self.buffer.len() > self.window_size {
-            let _ = self.buffer.pop_front();
-        }
-
-        Some(self.buffer.iter().cloned().collect())
-    }
-}
-
-impl<I: ExactSizeIterator> ExactSizeIterator for OverlappingWindowIter<I>
-where
-    I::Item: Clone,
-{
-    fn len(&self) -> usize {
-        (self.iterator.len() - self.window_size) / self.step_size
-    }
-}
-
-pub trait IterExt<I: Iterator> {
-    fn overlapping_windows(self, window_size: usize, overlap: usize) -> OverlappingWindowIter<I>;
+/// Windows of `window_size` samples that lie entirely within one of `ranges` (clamped to `len`),
+/// so that no window spans the gap between two of them.
+pub fn windows_in_ranges(
+    ranges: &[Range<usize>],
+    len: usize,
+    window_size: usize,
+    step: usize,
+) -> impl Iterator<Item = Range<usize>> + '_ {
+    ranges.iter().flat_map(move |range| {
+        windows_in(range.start.min(len)..range.end.min(len), window_size, step)
+    })
 }
 
-impl<I> IterExt<I> for I
-where
-    I: Iterator + Sized,
-{
-    fn overlapping_windows(self, window_size: usize, overlap: usize) -> OverlappingWindowIter<I> {
-        OverlappingWindowIter::new(self, window_size, overlap)
-    }
+/// The values at the indices in `ranges`, for statistics that don't depend on the order of the
+/// samples.
+pub fn values_in_ranges<T: Copy>(values: &[T], ranges: &[Range<usize>]) -> Vec<T> {
+    ranges
+        .iter()
+        .flat_map(|range| &values[range.start.min(values.len())..range.end.min(values.len())])
+        .copied()
+        .collect()
 }
@@ -0,0 +1,190 @@
+use std::ops::Range;
+
+use crate::flight_data::FlightData;
+use crate::iter::values_in_ranges;
+use crate::utils::median;
+
+/// I-term windup threshold relative to the median absolute I-term of an axis
+const THRESHOLD_FACTOR: f32 = 4.0;
+/// Lowest threshold, so calm flights don't produce events from tiny I-term changes
+const MIN_THRESHOLD: f32 = 20.0;
+/// Shortest accumulation that counts as windup
+const MIN_DURATION: f64 = 0.15;
+/// Runs above the threshold closer together than this are merged into one event
+const MAX_GAP: f64 = 0.05;
+/// Time before an event in which a fast stick movement is blamed for the windup
+const LOOKBACK: f64 = 0.5;
+/// Setpoint rate in °/s above which a manoeuvre counts as a flip or roll
+const FLIP_RATE: f32 = 500.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum WindupKind {
+    /// After a fast manoeuvre, usually visible as bounce-back
+    BounceBack,
+    /// Without large stick inputs, e.g. from wind or an unbalanced frame
+    Drift,
+}
+
+impl std::fmt::Display for WindupKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BounceBack => write!(f, "Bounce-back"),
+            Self::Drift => write!(f, "Drift"),
+        }
+    }
+}
+
+pub struct WindupEvent {
+    pub axis: usize,
+    pub start: f64,
+    pub end: f64,
+    /// I-term with the largest magnitude during the event
+    pub peak: f32,
+    /// Highest absolute setpoint rate during and shortly before the event
+    pub max_setpoint_rate: f32,
+    pub kind: WindupKind,
+}
+
+pub struct ItermAnalysis {
+    pub events: Vec<WindupEvent>,
+    pub thresholds: [f32; 3],
+    /// I-term during windup events, zero everywhere else
+    pub indicators: [Vec<f32>; 3],
+}
+
+/// Betaflight's I-term relax settings, see `iterm_relax` in the firmware's pid settings.
+pub struct ItermRelax {
+    pub mode: Option<u8>,
+    pub relax_type: Option<u8>,
+    pub cutoff: Option<f32>,
+}
+
+impl ItermRelax {
+    pub fn from_headers(fd: &FlightData) -> Self {
+        Self {
+            mode: fd.header_value("iterm_relax"),
+            relax_type: fd.header_value("iterm_relax_type"),
+            cutoff: fd.header_value("iterm_relax_cutoff"),
+        }
+    }
+
+    /// Whether I-term relax is active on an axis, `None` if the log doesn't say.
+    pub fn applies_to(&self, axis: usize) -> Option<bool> {
+        match self.mode? {
+            0 => Some(false),
+            1 | 3 => Some(axis < 2),
+            2 | 4 => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn mode_name(&self) -> Option<&'static str> {
+        match self.mode? {
+            0 => Some("Off"),
+            1 => Some("RP"),
+            2 => Some("RPY"),
+            3 => Some("RP (increment only)"),
+            4 => Some("RPY (increment only)"),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> Option<&'static str> {
+        match self.relax_type? {
+            0 => Some("Gyro"),
+            1 => Some("Setpoint"),
+            _ => None,
+        }
+    }
+}
+
+/// Median of the absolute values, which unlike the RMS isn't inflated by the windup itself
+fn median_abs(values: &[f32]) -> f32 {
+    median(values.iter().map(|v| v.abs() as f64).collect()).unwrap_or_default() as f32
+}
+
+/// Index ranges in which `|values|` exceeds `threshold`, with short gaps merged.
+fn runs_above(times: &[f64], values: &[f32], threshold: f32) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut push_run = |start: usize, end: usize| match runs.last_mut() {
+        Some(last) if times[start] - times[last.1 - 1] < MAX_GAP => last.1 = end,
+        _ => runs.push((start, end)),
+    };
+
+    let mut start = None;
+    for (i, value) in values.iter().enumerate() {
+        match (value.abs() > threshold, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                push_run(s, i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        push_run(s, values.len());
+    }
+    runs
+}
+
+/// Finds periods of sustained I-term accumulation on each axis that start within `ranges`.
+pub fn detect_windup(
+    times: &[f64],
+    iterm: [&[f32]; 3],
+    setpoint: [&[f32]; 3],
+    ranges: &[Range<usize>],
+) -> ItermAnalysis {
+    let mut events = Vec::new();
+    let mut thresholds = [0.0; 3];
+    let mut indicators = [0, 1, 2].map(|_| vec![0.0; times.len()]);
+
+    for axis in 0..3 {
+        let len = times.len().min(iterm[axis].len()).min(setpoint[axis].len());
+        let values = &iterm[axis][..len];
+        let threshold = f32::max(
+            MIN_THRESHOLD,
+            THRESHOLD_FACTOR * median_abs(&values_in_ranges(values, ranges)),
+        );
+        thresholds[axis] = threshold;
+
+        for (start, end) in runs_above(times, values, threshold) {
+            if times[end - 1] - times[start] < MIN_DURATION
+                || !ranges.iter().any(|range| range.contains(&start))
+            {
+                continue;
+            }
+
+            let lookback_start = times[..start].partition_point(|t| *t < times[start] - LOOKBACK);
+            let max_setpoint_rate = setpoint[axis][lookback_start..end]
+                .iter()
+                .fold(0.0f32, |max, s| max.max(s.abs()));
+            let peak = values[start..end]
+                .iter()
+                .copied()
+                .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+                .unwrap_or_default();
+
+            events.push(WindupEvent {
+                axis,
+                start: times[start],
+                end: times[end - 1],
+                peak,
+                max_setpoint_rate,
+                kind: if max_setpoint_rate >= FLIP_RATE {
+                    WindupKind::BounceBack
+                } else {
+                    WindupKind::Drift
+                },
+            });
+            indicators[axis][start..end].copy_from_slice(&values[start..end]);
+        }
+    }
+
+    events.sort_by(|a, b| a.start.total_cmp(&b.start));
+    ItermAnalysis {
+        events,
+        thresholds,
+        indicators,
+    }
+}
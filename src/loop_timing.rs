@@ -0,0 +1,129 @@
+/// Intervals longer than this multiple of the expected one contain missed frames
+const MISSED_FRAME_FACTOR: f64 = 1.5;
+/// Number of histogram bins on either side of the expected interval
+const HISTOGRAM_HALF_BINS: usize = 25;
+/// Histogram covers the expected interval ± this fraction of it
+const HISTOGRAM_RANGE: f64 = 0.5;
+
+/// Frames missing between two logged ones.
+pub struct MissedFrames {
+    /// Time of the frame after the gap, in seconds
+    pub time: f64,
+    pub count: usize,
+}
+
+pub struct LoopTiming {
+    /// Interval between frames expected from the headers, in seconds
+    pub configured_interval: Option<f64>,
+    pub median_interval: f64,
+    pub mean_interval: f64,
+    /// Standard deviation of the intervals, in seconds
+    pub jitter: f64,
+    pub min_interval: f64,
+    pub max_interval: f64,
+    pub p99_interval: f64,
+    /// (interval in seconds, share of intervals in %), centered on the expected interval.
+    /// Intervals outside of the range are counted in the outermost bins.
+    pub histogram: Vec<(f64, f64)>,
+    pub bin_width: f64,
+    pub missed: Vec<MissedFrames>,
+    /// Interval before each frame in µs, zero for the first one
+    pub intervals: Vec<f32>,
+    /// Length of the gap before each frame in µs if frames are missing there, zero otherwise, so
+    /// it can be drawn on the same axis as `intervals`
+    pub missed_indicator: Vec<f32>,
+}
+
+impl LoopTiming {
+    pub fn missed_total(&self) -> usize {
+        self.missed.iter().map(|m| m.count).sum()
+    }
+
+    /// Share of the frames that should have been logged but are missing, in %.
+    pub fn missed_share(&self, logged_frames: usize) -> f64 {
+        let missed = self.missed_total();
+        let expected = logged_frames + missed;
+        if expected == 0 {
+            0.0
+        } else {
+            100.0 * missed as f64 / expected as f64
+        }
+    }
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index]
+}
+
+/// Analyzes the intervals between the logged frames, against the interval configured in the
+/// headers if known and the median interval otherwise.
+pub fn analyze_loop_timing(times: &[f64], configured_rate: Option<f64>) -> Option<LoopTiming> {
+    let intervals: Vec<f64> = times.windows(2).map(|w| w[1] - w[0]).collect();
+    if intervals.is_empty() {
+        return None;
+    }
+
+    let mut sorted = intervals.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median_interval = percentile(&sorted, 0.5);
+    let mean_interval = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let jitter = (intervals
+        .iter()
+        .map(|i| (i - mean_interval).powi(2))
+        .sum::<f64>()
+        / intervals.len() as f64)
+        .sqrt();
+
+    let configured_interval = configured_rate
+        .filter(|rate| *rate > 0.0)
+        .map(|rate| 1.0 / rate);
+    let expected = configured_interval.unwrap_or(median_interval);
+
+    let bins = 2 * HISTOGRAM_HALF_BINS + 1;
+    let bin_width = 2.0 * HISTOGRAM_RANGE * expected / bins as f64;
+    let low = expected - bin_width * (HISTOGRAM_HALF_BINS as f64 + 0.5);
+    let mut counts = vec![0usize; bins];
+    for interval in intervals.iter() {
+        let bin = ((interval - low) / bin_width).floor() as isize;
+        counts[bin.clamp(0, bins as isize - 1) as usize] += 1;
+    }
+    let histogram = counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let center = low + (i as f64 + 0.5) * bin_width;
+            (center, 100.0 * count as f64 / intervals.len() as f64)
+        })
+        .collect();
+
+    let mut missed = Vec::new();
+    let mut missed_indicator = vec![0.0; times.len()];
+    for (i, interval) in intervals.iter().enumerate() {
+        if *interval > MISSED_FRAME_FACTOR * expected {
+            let count = usize::max(1, (interval / expected).round() as usize - 1);
+            missed.push(MissedFrames {
+                time: times[i + 1],
+                count,
+            });
+            missed_indicator[i + 1] = (interval * 1_000_000.0) as f32;
+        }
+    }
+
+    Some(LoopTiming {
+        configured_interval,
+        median_interval,
+        mean_interval,
+        jitter,
+        min_interval: sorted[0],
+        max_interval: sorted[sorted.len() - 1],
+        p99_interval: percentile(&sorted, 0.99),
+        histogram,
+        bin_width,
+        missed,
+        intervals: std::iter::once(0.0)
+            .chain(intervals.iter().map(|i| (i * 1_000_000.0) as f32))
+            .collect(),
+        missed_indicator,
+    })
+}
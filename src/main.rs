@@ -1,11 +1,30 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+mod battery;
+mod crash;
+mod expr;
+mod feedforward;
+mod filters;
 mod flight_data;
+mod flight_phases;
 mod gui;
 mod iter;
+mod iterm;
 mod log_file;
+mod loop_timing;
+mod motor_health;
+mod noise_sources;
+mod pid_contribution;
+mod propwash;
+mod rates;
+mod saturation;
+mod sensor_clipping;
+mod spectrum;
 mod step_response;
+mod stick_usage;
+mod tracking_error;
 mod utils;
+mod vibration;
 
 use gui::App;
 use std::path::PathBuf;
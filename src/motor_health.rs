@@ -0,0 +1,176 @@
+/// Number of bins over the motor output range for comparing eRPM at equal output
+const OUTPUT_BINS: usize = 20;
+/// Minimum number of samples per motor in a bin for it to be compared
+const MIN_BIN_SAMPLES: usize = 50;
+/// Length of the windows in which the hardest working motor is determined
+const WINDOW_DURATION: f64 = 1.0;
+/// A motor whose mean output is this far (in % of the output range) above the average of the
+/// others, while being the hardest working motor in most windows, is flagged
+const OUTPUT_EXCESS_WARNING: f32 = 3.0;
+const HARDEST_SHARE_WARNING: f32 = 60.0;
+/// eRPM deviation at equal output (in %) from which a motor is flagged
+const RPM_DEVIATION_WARNING: f32 = 5.0;
+
+pub struct MotorHealth {
+    /// Mean output in % of the output range
+    pub mean_output: f32,
+    /// Standard deviation of the output in % of the output range
+    pub output_std: f32,
+    /// eRPM compared to the other motors at equal output in %, if eRPM was logged
+    pub rpm_deviation: Option<f32>,
+    /// Share of windows in which this motor had the highest mean output in %
+    pub hardest_working: f32,
+}
+
+pub struct MotorHealthAnalysis {
+    pub motors: Vec<MotorHealth>,
+}
+
+impl MotorHealthAnalysis {
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let count = self.motors.len();
+        if count < 2 {
+            return warnings;
+        }
+
+        let total_output: f32 = self.motors.iter().map(|m| m.mean_output).sum();
+        for (i, motor) in self.motors.iter().enumerate() {
+            let others = (total_output - motor.mean_output) / (count - 1) as f32;
+            let excess = motor.mean_output - others;
+            if excess >= OUTPUT_EXCESS_WARNING && motor.hardest_working >= HARDEST_SHARE_WARNING {
+                warnings.push(format!(
+                    "motor[{}] works {:.1}% harder than the others and is the hardest working \
+                     motor {:.0}% of the time. Check for a weak motor, damaged prop or CG offset.",
+                    i, excess, motor.hardest_working
+                ));
+            }
+
+            if let Some(deviation) = motor.rpm_deviation {
+                if deviation.abs() >= RPM_DEVIATION_WARNING {
+                    warnings.push(format!(
+                        "motor[{}] spins {:.1}% {} than the others at equal output. Check its \
+                         bearings and prop.",
+                        i,
+                        deviation.abs(),
+                        if deviation < 0.0 { "slower" } else { "faster" }
+                    ));
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+fn mean_std(values: &[f32]) -> (f32, f32) {
+    let n = values.len().max(1) as f64;
+    let mean = values.iter().map(|v| *v as f64).sum::<f64>() / n;
+    let variance = values
+        .iter()
+        .map(|v| (*v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    (mean as f32, variance.sqrt() as f32)
+}
+
+/// Mean eRPM per motor and output bin, `None` for bins with too few samples.
+fn rpm_per_bin(outputs: &[Vec<f32>], erpm: &[&Vec<f32>]) -> Vec<Vec<Option<f64>>> {
+    outputs
+        .iter()
+        .zip(erpm.iter())
+        .map(|(output, erpm)| {
+            let mut sums = vec![(0.0, 0usize); OUTPUT_BINS];
+            for (o, e) in output.iter().zip(erpm.iter()) {
+                let bin = ((o / 100.0 * OUTPUT_BINS as f32) as usize).min(OUTPUT_BINS - 1);
+                sums[bin].0 += *e as f64;
+                sums[bin].1 += 1;
+            }
+            sums.into_iter()
+                .map(|(sum, count)| (count >= MIN_BIN_SAMPLES).then(|| sum / count as f64))
+                .collect()
+        })
+        .collect()
+}
+
+fn rpm_deviations(outputs: &[Vec<f32>], erpm: &[&Vec<f32>]) -> Vec<Option<f32>> {
+    let bins = rpm_per_bin(outputs, erpm);
+    let common_bins: Vec<(usize, f64)> = (0..OUTPUT_BINS)
+        .filter_map(|b| {
+            let values: Option<Vec<f64>> = bins.iter().map(|m| m[b]).collect();
+            let values = values?;
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            (mean > 0.0).then_some((b, mean))
+        })
+        .collect();
+
+    bins.iter()
+        .map(|motor| {
+            if common_bins.is_empty() {
+                return None;
+            }
+
+            let deviation = common_bins
+                .iter()
+                .map(|(b, mean)| motor[*b].unwrap_or_default() / mean - 1.0)
+                .sum::<f64>()
+                / common_bins.len() as f64;
+            Some((deviation * 100.0) as f32)
+        })
+        .collect()
+}
+
+/// Compares the motors against each other to find one that has to work harder.
+pub fn analyze_motor_health(
+    times: &[f64],
+    motors: &[&Vec<f32>],
+    erpm: Option<&[&Vec<f32>]>,
+    output_range: (f32, f32),
+) -> MotorHealthAnalysis {
+    let (min, max) = output_range;
+    let outputs: Vec<Vec<f32>> = motors
+        .iter()
+        .map(|m| m.iter().map(|v| 100.0 * (v - min) / (max - min)).collect())
+        .collect();
+
+    let len = outputs.iter().map(|o| o.len()).min().unwrap_or_default();
+    let mut hardest_counts = vec![0usize; outputs.len()];
+    let mut windows = 0;
+    let mut start = 0;
+    while start < len {
+        let end =
+            start + times[start..len].partition_point(|t| *t < times[start] + WINDOW_DURATION);
+        let hardest = outputs
+            .iter()
+            .map(|o| o[start..end].iter().sum::<f32>())
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((motor, _)) = hardest {
+            hardest_counts[motor] += 1;
+            windows += 1;
+        }
+        start = end.max(start + 1);
+    }
+
+    let rpm_deviations = erpm
+        .filter(|erpm| erpm.len() == motors.len())
+        .map(|erpm| rpm_deviations(&outputs, erpm))
+        .unwrap_or_else(|| vec![None; outputs.len()]);
+
+    let motors = outputs
+        .iter()
+        .zip(hardest_counts)
+        .zip(rpm_deviations)
+        .map(|((output, hardest), rpm_deviation)| {
+            let (mean_output, output_std) = mean_std(output);
+            MotorHealth {
+                mean_output,
+                output_std,
+                rpm_deviation,
+                hardest_working: 100.0 * hardest as f32 / windows.max(1) as f32,
+            }
+        })
+        .collect();
+
+    MotorHealthAnalysis { motors }
+}
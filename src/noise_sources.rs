@@ -0,0 +1,331 @@
+use std::fmt::Display;
+
+use crate::spectrum::PowerSpectrum;
+
+pub const THROTTLE_BANDS: usize = 10;
+/// Throttle bands with fewer spectra than this are ignored, their averages are too noisy.
+const MIN_SPECTRA_PER_BAND: usize = 10;
+const MAX_PEAKS_PER_BAND: usize = 8;
+/// Peaks below this are mostly flight movement rather than noise.
+const MIN_PEAK_FREQUENCY: f64 = 30.0;
+/// Maximum relative frequency change for peaks in neighbouring bands to count as the same peak.
+const TRACK_TOLERANCE: f64 = 0.15;
+/// Maximum relative frequency spread of a peak across all throttle bands to count as fixed.
+const FIXED_FREQUENCY_TOLERANCE: f64 = 0.15;
+/// Maximum relative distance of a peak from a motor harmonic to be attributed to it.
+const HARMONIC_TOLERANCE: f64 = 0.1;
+const MAX_HARMONIC: usize = 3;
+/// Minimum rise (in dB) over the quietest throttle band to count as broadband noise.
+const BROADBAND_MIN_RISE: f64 = 6.0;
+const BROADBAND_MIN_WIDTH: f64 = 50.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NoiseSource {
+    FrameResonance,
+    /// Noise following the motor speed, with the matching harmonic if eRPM was logged.
+    MotorNoise(Option<usize>),
+    /// Broadband noise rising with throttle, typically props or propwash.
+    Broadband,
+}
+
+impl Display for NoiseSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FrameResonance => write!(f, "Frame resonance"),
+            Self::MotorNoise(Some(1)) => write!(f, "Motor noise (1st harmonic)"),
+            Self::MotorNoise(Some(2)) => write!(f, "Motor noise (2nd harmonic)"),
+            Self::MotorNoise(Some(3)) => write!(f, "Motor noise (3rd harmonic)"),
+            Self::MotorNoise(Some(h)) => write!(f, "Motor noise ({}th harmonic)", h),
+            Self::MotorNoise(None) => write!(f, "Motor noise"),
+            Self::Broadband => write!(f, "Props / propwash"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NoiseFinding {
+    pub source: NoiseSource,
+    pub frequency_range: (f64, f64),
+    /// Throttle (0-1000) at which the noise is strongest
+    pub worst_throttle: f32,
+    /// Rise above the noise floor in dB
+    pub severity: f64,
+}
+
+pub fn throttle_band(throttle: f32) -> usize {
+    let band = ((throttle / 1000.0) * THROTTLE_BANDS as f32) as usize;
+    usize::min(band, THROTTLE_BANDS - 1)
+}
+
+fn band_center(band: usize) -> f32 {
+    ((band as f32) + 0.5) * 1000.0 / (THROTTLE_BANDS as f32)
+}
+
+/// Mean of `values` for every throttle band, e.g. to get the motor frequency per band.
+pub fn throttle_band_means(throttle: &[f32], values: &[f32]) -> [Option<f32>; THROTTLE_BANDS] {
+    let mut sums = [(0.0, 0usize); THROTTLE_BANDS];
+    for (t, v) in throttle.iter().zip(values.iter()) {
+        let band = &mut sums[throttle_band(*t)];
+        band.0 += v;
+        band.1 += 1;
+    }
+
+    sums.map(|(sum, count)| (count > 0).then(|| sum / (count as f32)))
+}
+
+struct TrackPoint {
+    band: usize,
+    frequency: f64,
+    severity: f64,
+}
+
+/// Classifies the noise in a set of spectra (throttle, power in dB with ascending frequency
+/// bins spaced `resolution` Hz apart), ranked by severity. Peaks that stay at the same frequency
+/// across throttle bands are frame resonances, peaks that move with throttle (or line up with
+/// a harmonic of `motor_frequencies`, per throttle band) are motor noise, and rises of the
+/// whole spectrum with throttle are attributed to props or propwash.
+pub fn identify_noise_sources<I, S>(
+    spectra: I,
+    resolution: f64,
+    motor_frequencies: Option<&[Option<f32>; THROTTLE_BANDS]>,
+) -> Vec<NoiseFinding>
+where
+    I: IntoIterator<Item = (f32, S)>,
+    S: IntoIterator<Item = f32>,
+{
+    let mut sums: Vec<Vec<(f64, usize)>> = vec![Vec::new(); THROTTLE_BANDS];
+    let mut counts = [0usize; THROTTLE_BANDS];
+    for (throttle, spectrum) in spectra {
+        let band = throttle_band(throttle);
+        counts[band] += 1;
+
+        for (i, value) in spectrum.into_iter().enumerate() {
+            if i >= sums[band].len() {
+                sums[band].push((0.0, 0));
+            }
+
+            if value.is_finite() {
+                sums[band][i].0 += value as f64;
+                sums[band][i].1 += 1;
+            }
+        }
+    }
+
+    let band_spectra: Vec<Option<Vec<f64>>> = sums
+        .into_iter()
+        .zip(counts)
+        .map(|(sums, count)| {
+            (count >= MIN_SPECTRA_PER_BAND).then(|| {
+                sums.into_iter()
+                    .map(|(sum, n)| if n > 0 { sum / (n as f64) } else { f64::NAN })
+                    .collect()
+            })
+        })
+        .collect();
+
+    let min_bin = (MIN_PEAK_FREQUENCY / resolution).ceil() as usize;
+    let mut findings = find_peak_tracks(&band_spectra, resolution, min_bin)
+        .into_iter()
+        .filter_map(|track| classify_track(track, resolution, motor_frequencies))
+        .collect::<Vec<_>>();
+    findings.extend(find_broadband_rise(&band_spectra, resolution, min_bin));
+
+    findings.sort_by(|a, b| b.severity.total_cmp(&a.severity));
+    findings
+}
+
+/// Finds peaks in every throttle band and links peaks of similar frequency in neighbouring
+/// bands into tracks.
+fn find_peak_tracks(
+    band_spectra: &[Option<Vec<f64>>],
+    resolution: f64,
+    min_bin: usize,
+) -> Vec<Vec<TrackPoint>> {
+    let mut tracks: Vec<Vec<TrackPoint>> = Vec::new();
+
+    for (band, spectrum) in band_spectra.iter().enumerate() {
+        let Some(spectrum) = spectrum else { continue };
+
+        let mut floor: Vec<f64> = spectrum
+            .iter()
+            .skip(min_bin)
+            .copied()
+            .filter(|v| v.is_finite())
+            .collect();
+        if floor.is_empty() {
+            continue;
+        }
+        floor.sort_by(f64::total_cmp);
+        let floor = floor[floor.len() / 2];
+
+        let power = PowerSpectrum {
+            resolution,
+            density: spectrum
+                .iter()
+                .map(|db| {
+                    if db.is_finite() {
+                        10f64.powf(db / 10.0)
+                    } else {
+                        0.0
+                    }
+                })
+                .collect(),
+        };
+
+        for (frequency, db) in power.peaks(MAX_PEAKS_PER_BAND) {
+            if frequency < MIN_PEAK_FREQUENCY {
+                continue;
+            }
+
+            let point = TrackPoint {
+                band,
+                frequency,
+                severity: db - floor,
+            };
+
+            // Continue the closest track ending in one of the previous two bands.
+            let closest = tracks
+                .iter_mut()
+                .filter(|track| {
+                    let last = track.last().unwrap();
+                    last.band < band && band - last.band <= 2
+                })
+                .map(|track| {
+                    let last = track.last().unwrap().frequency;
+                    ((frequency - last).abs() / last, track)
+                })
+                .filter(|(distance, _)| *distance < TRACK_TOLERANCE)
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+
+            match closest {
+                Some((_, track)) => track.push(point),
+                None => tracks.push(vec![point]),
+            }
+        }
+    }
+
+    tracks
+}
+
+fn classify_track(
+    track: Vec<TrackPoint>,
+    resolution: f64,
+    motor_frequencies: Option<&[Option<f32>; THROTTLE_BANDS]>,
+) -> Option<NoiseFinding> {
+    let harmonic = motor_frequencies.and_then(|motor_frequencies| {
+        (1..=MAX_HARMONIC).find(|h| {
+            let matching = track
+                .iter()
+                .filter(|p| {
+                    motor_frequencies[p.band]
+                        .map(|f| (*h as f64) * (f as f64))
+                        .filter(|f| *f > 0.0)
+                        .map(|f| (p.frequency - f).abs() / f < HARMONIC_TOLERANCE)
+                        .unwrap_or(false)
+                })
+                .count();
+            matching * 2 > track.len()
+        })
+    });
+
+    let min = track
+        .iter()
+        .map(|p| p.frequency)
+        .fold(f64::INFINITY, f64::min);
+    let max = track.iter().map(|p| p.frequency).fold(0.0, f64::max);
+    let mean = track.iter().map(|p| p.frequency).sum::<f64>() / (track.len() as f64);
+    let first = track.first()?;
+    let last = track.last()?;
+
+    let source = if harmonic.is_some() {
+        NoiseSource::MotorNoise(harmonic)
+    } else if track.len() < 2 {
+        // A peak seen at a single throttle can't be classified without eRPM data.
+        return None;
+    } else if (max - min) / mean < FIXED_FREQUENCY_TOLERANCE {
+        NoiseSource::FrameResonance
+    } else if last.frequency > first.frequency {
+        NoiseSource::MotorNoise(None)
+    } else {
+        return None;
+    };
+
+    let worst = track
+        .iter()
+        .max_by(|a, b| a.severity.total_cmp(&b.severity))?;
+    Some(NoiseFinding {
+        source,
+        frequency_range: (min - resolution, max + resolution),
+        worst_throttle: band_center(worst.band),
+        severity: worst.severity,
+    })
+}
+
+/// Compares every throttle band to the quietest level seen at each frequency across all bands
+/// and reports the widest range of frequencies that rises above it in the loudest band.
+fn find_broadband_rise(
+    band_spectra: &[Option<Vec<f64>>],
+    resolution: f64,
+    min_bin: usize,
+) -> Option<NoiseFinding> {
+    let present: Vec<(usize, &Vec<f64>)> = band_spectra
+        .iter()
+        .enumerate()
+        .filter_map(|(band, spectrum)| spectrum.as_ref().map(|s| (band, s)))
+        .collect();
+    if present.len() < 2 {
+        return None;
+    }
+
+    let bins = present.iter().map(|(_, s)| s.len()).min()?;
+    let baseline: Vec<f64> = (0..bins)
+        .map(|i| {
+            present
+                .iter()
+                .map(|(_, s)| s[i])
+                .filter(|v| v.is_finite())
+                .fold(f64::INFINITY, f64::min)
+        })
+        .collect();
+
+    present
+        .iter()
+        .filter_map(|(band, spectrum)| {
+            let excess: Vec<f64> = (0..bins)
+                .map(|i| spectrum[i] - baseline[i])
+                .map(|e| if e.is_finite() { e } else { 0.0 })
+                .collect();
+
+            // widest run of bins that rise far enough above the baseline
+            let mut runs = Vec::new();
+            let mut start = None;
+            for (i, e) in excess.iter().enumerate().skip(min_bin) {
+                match (*e >= BROADBAND_MIN_RISE, start) {
+                    (true, None) => start = Some(i),
+                    (false, Some(s)) => {
+                        runs.push((s, i));
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(s) = start {
+                runs.push((s, bins));
+            }
+
+            let (start, end) = runs.into_iter().max_by_key(|(s, e)| e - s)?;
+            let width = ((end - start) as f64) * resolution;
+            if width < BROADBAND_MIN_WIDTH {
+                return None;
+            }
+
+            let severity = excess[start..end].iter().sum::<f64>() / ((end - start) as f64);
+            Some(NoiseFinding {
+                source: NoiseSource::Broadband,
+                frequency_range: ((start as f64) * resolution, (end as f64) * resolution),
+                worst_throttle: band_center(*band),
+                severity,
+            })
+        })
+        .max_by(|a, b| a.severity.total_cmp(&b.severity))
+}
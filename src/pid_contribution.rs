@@ -0,0 +1,123 @@
+use std::ops::Range;
+
+use crate::iter::{values_in_ranges, windows_in_ranges};
+use crate::spectrum::{welch_psd, PowerSpectrum};
+use crate::utils::rms;
+
+/// Length of the windows in which the share of each term over time is calculated
+const SHARE_WINDOW_DURATION: f64 = 0.1;
+const SPECTRUM_SEGMENT_SIZE: usize = 1024;
+/// Components above this frequency are unlikely to be caused by the pilot's inputs
+pub const NOISE_FREQUENCY: f64 = 100.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PidTerm {
+    P,
+    I,
+    D,
+    F,
+}
+
+impl PidTerm {
+    pub const ALL: [Self; 4] = [Self::P, Self::I, Self::D, Self::F];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::P => "P",
+            Self::I => "I",
+            Self::D => "D",
+            Self::F => "F",
+        }
+    }
+}
+
+pub struct TermContribution {
+    pub term: PidTerm,
+    pub rms: f32,
+    /// Share of the summed absolute output of all terms in %
+    pub share: f32,
+    /// (time, share in %) per window
+    pub share_over_time: Vec<(f64, f64)>,
+    pub spectrum: Option<PowerSpectrum>,
+    /// Share of the term's power above `NOISE_FREQUENCY` in %
+    pub noise_share: Option<f32>,
+}
+
+/// Breaks the PID output of one axis down into the contributions of its terms, within `ranges`.
+pub fn calculate_pid_contribution(
+    times: &[f64],
+    terms: &[(PidTerm, &[f32])],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> Vec<TermContribution> {
+    let abs_sum = |values: &[f32]| values.iter().map(|v| v.abs() as f64).sum::<f64>();
+    let selected: Vec<Vec<f32>> = terms
+        .iter()
+        .map(|(_, values)| values_in_ranges(values, ranges))
+        .collect();
+    let total: f64 = selected.iter().map(|values| abs_sum(values)).sum();
+
+    let window = usize::max(1, (SHARE_WINDOW_DURATION * sample_rate) as usize);
+    let len = terms
+        .iter()
+        .map(|(_, values)| values.len())
+        .min()
+        .unwrap_or_default()
+        .min(times.len());
+    let windows: Vec<Range<usize>> = windows_in_ranges(ranges, len, window, window).collect();
+    let window_totals: Vec<f64> = windows
+        .iter()
+        .map(|range| {
+            terms
+                .iter()
+                .map(|(_, values)| abs_sum(&values[range.clone()]))
+                .sum()
+        })
+        .collect();
+
+    terms
+        .iter()
+        .zip(selected.iter())
+        .map(|((term, values), selected)| {
+            let rms = rms(selected.iter().copied()).unwrap_or_default();
+            let share = if total > 0.0 {
+                (100.0 * abs_sum(selected) / total) as f32
+            } else {
+                0.0
+            };
+
+            let share_over_time = windows
+                .iter()
+                .zip(window_totals.iter())
+                .map(|(range, window_total)| {
+                    let time = (times[range.start] + times[range.end - 1]) / 2.0;
+                    let share = if *window_total > 0.0 {
+                        100.0 * abs_sum(&values[range.clone()]) / window_total
+                    } else {
+                        0.0
+                    };
+                    (time, share)
+                })
+                .collect();
+
+            let spectrum = welch_psd(values, ranges, sample_rate, SPECTRUM_SEGMENT_SIZE);
+            let noise_share = spectrum.as_ref().and_then(|spectrum| {
+                let total = spectrum.band_rms(0.0, f64::INFINITY)?.powi(2);
+                let noise = spectrum
+                    .band_rms(NOISE_FREQUENCY, f64::INFINITY)
+                    .unwrap_or_default()
+                    .powi(2);
+                (total > 0.0).then(|| (100.0 * noise / total) as f32)
+            });
+
+            TermContribution {
+                term: *term,
+                rms,
+                share,
+                share_over_time,
+                spectrum,
+                noise_share,
+            }
+        })
+        .collect()
+}
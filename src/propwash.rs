@@ -0,0 +1,180 @@
+use std::ops::Range;
+
+use crate::filters::band_pass;
+
+/// Frequency band in which propwash oscillations show up
+const OSCILLATION_BAND: (f64, f64) = (20.0, 100.0);
+/// Minimum throttle drop (on the 0-1000 scale) within `DROP_WINDOW` to count as a manoeuvre
+const THROTTLE_DROP: f32 = 150.0;
+/// Minimum drop of the combined roll/pitch setpoint in °/s within `DROP_WINDOW`, i.e. the end of
+/// a flip or roll
+const RATE_DROP: f32 = 300.0;
+const DROP_WINDOW: f64 = 0.1;
+/// Time after a manoeuvre in which an oscillation has to start
+const SEARCH_WINDOW: f64 = 0.3;
+/// Window for the RMS envelope of the oscillation
+const ENVELOPE_WINDOW: f64 = 0.02;
+/// Envelope amplitude in °/s above which the quad is considered to be oscillating
+const MIN_AMPLITUDE: f32 = 15.0;
+const MIN_DURATION: f64 = 0.03;
+
+pub struct PropwashEvent {
+    pub start: f64,
+    pub end: f64,
+    /// Highest RMS oscillation amplitude in °/s
+    pub peak_amplitude: f32,
+    /// Oscillation amplitude integrated over the duration of the event in °
+    pub severity: f32,
+}
+
+impl PropwashEvent {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+pub struct PropwashAnalysis {
+    pub events: Vec<PropwashEvent>,
+    /// Sharp throttle or rate drops that could cause propwash
+    pub manoeuvres: usize,
+    /// Roll and pitch oscillation envelopes during events, zero everywhere else
+    pub indicators: [Vec<f32>; 2],
+}
+
+impl PropwashAnalysis {
+    /// Average severity per manoeuvre, manoeuvres without oscillation count as zero.
+    pub fn score(&self) -> f32 {
+        if self.manoeuvres == 0 {
+            return 0.0;
+        }
+
+        self.events.iter().map(|e| e.severity).sum::<f32>() / self.manoeuvres as f32
+    }
+
+    pub fn total_duration(&self) -> f64 {
+        self.events.iter().map(|e| e.duration()).sum()
+    }
+
+    pub fn worst_event(&self) -> Option<&PropwashEvent> {
+        self.events
+            .iter()
+            .max_by(|a, b| a.severity.total_cmp(&b.severity))
+    }
+}
+
+/// RMS of the band-passed gyro over a centered window.
+fn oscillation_envelope(gyro: &[f32], sample_rate: f64) -> Vec<f32> {
+    let filtered = band_pass(gyro, sample_rate, OSCILLATION_BAND.0, OSCILLATION_BAND.1);
+    let mut cumulative = Vec::with_capacity(filtered.len() + 1);
+    cumulative.push(0.0);
+    for value in filtered.iter() {
+        cumulative.push(cumulative.last().unwrap() + (value * value) as f64);
+    }
+
+    let half_window = ((ENVELOPE_WINDOW * sample_rate / 2.0) as usize).max(1);
+    (0..filtered.len())
+        .map(|i| {
+            let start = i.saturating_sub(half_window);
+            let end = usize::min(i + half_window + 1, filtered.len());
+            let mean = (cumulative[end] - cumulative[start]) / (end - start) as f64;
+            mean.max(0.0).sqrt() as f32
+        })
+        .collect()
+}
+
+/// Looks for manoeuvres within `ranges`, i.e. sharp throttle drops or the end of flips and rolls,
+/// followed by roll/pitch oscillations in the 20-100Hz band.
+pub fn detect_propwash(
+    times: &[f64],
+    throttle: &[f32],
+    setpoint: [&[f32]; 2],
+    gyro: [&[f32]; 2],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> PropwashAnalysis {
+    let len = times
+        .len()
+        .min(throttle.len())
+        .min(setpoint[0].len())
+        .min(setpoint[1].len())
+        .min(gyro[0].len())
+        .min(gyro[1].len());
+    if len == 0 || sample_rate <= 0.0 {
+        return PropwashAnalysis {
+            events: Vec::new(),
+            manoeuvres: 0,
+            indicators: [vec![0.0; times.len()], vec![0.0; times.len()]],
+        };
+    }
+
+    let rate: Vec<f32> = (0..len)
+        .map(|i| setpoint[0][i].hypot(setpoint[1][i]))
+        .collect();
+    let envelopes = gyro.map(|g| oscillation_envelope(&g[..len], sample_rate));
+    let envelope: Vec<f32> = envelopes[0]
+        .iter()
+        .zip(envelopes[1].iter())
+        .map(|(r, p)| r.hypot(*p))
+        .collect();
+    let mut indicators = [vec![0.0; times.len()], vec![0.0; times.len()]];
+    let mut events = Vec::new();
+    let mut manoeuvres = 0;
+
+    let mut lag = 0;
+    let mut i = 0;
+    for range in ranges {
+        // only manoeuvres within the range count, the oscillations may outlast it
+        i = i.max(range.start);
+        lag = lag.max(range.start);
+        while i < range.end.min(len) {
+            while times[i] - times[lag] > DROP_WINDOW {
+                lag += 1;
+            }
+
+            if throttle[lag] - throttle[i] < THROTTLE_DROP && rate[lag] - rate[i] < RATE_DROP {
+                i += 1;
+                continue;
+            }
+            manoeuvres += 1;
+
+            let search_end = times[i..len]
+                .iter()
+                .position(|t| t - times[i] > SEARCH_WINDOW)
+                .map(|p| i + p)
+                .unwrap_or(len);
+            let Some(start) = (i..search_end).find(|k| envelope[*k] > MIN_AMPLITUDE) else {
+                i = search_end;
+                lag = lag.max(i.saturating_sub(1));
+                continue;
+            };
+            let end = (start..len)
+                .find(|k| envelope[*k] <= MIN_AMPLITUDE)
+                .unwrap_or(len);
+
+            let duration = times[end - 1] - times[start];
+            if duration >= MIN_DURATION {
+                let amplitudes = &envelope[start..end];
+                let dt = duration / (end - start) as f64;
+                events.push(PropwashEvent {
+                    start: times[start],
+                    end: times[end - 1],
+                    peak_amplitude: amplitudes.iter().copied().fold(0.0, f32::max),
+                    severity: amplitudes.iter().sum::<f32>() * dt as f32,
+                });
+                for (indicator, envelope) in indicators.iter_mut().zip(envelopes.iter()) {
+                    indicator[start..end].copy_from_slice(&envelope[start..end]);
+                }
+            }
+
+            // skip the rest of this manoeuvre so it isn't detected again
+            i = end.max(search_end);
+            lag = lag.max(i.saturating_sub(1));
+        }
+    }
+
+    PropwashAnalysis {
+        events,
+        manoeuvres,
+        indicators,
+    }
+}
@@ -0,0 +1,226 @@
+use crate::flight_data::FlightData;
+use crate::utils::median;
+
+/// Full stick deflection in `rcCommand` units
+const RC_COMMAND_RANGE: f32 = 500.0;
+/// Betaflight's `RC_RATE_INCREMENTAL`, applied to rc rates above 2.0
+const RC_RATE_INCREMENTAL: f32 = 14.54;
+const DEFAULT_RATE_LIMIT: f32 = 1998.0;
+/// Number of stick deflection bins in which measured and configured rates are compared
+const COMPARISON_BINS: usize = 20;
+const MIN_BIN_SAMPLES: usize = 20;
+/// Deviation from the configured curve, relative to its maximum rate, that is flagged
+const MISMATCH_THRESHOLD: f32 = 0.1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RatesType {
+    Betaflight,
+    Raceflight,
+    Kiss,
+    Actual,
+    Quick,
+}
+
+impl RatesType {
+    fn from_header(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Betaflight),
+            1 => Some(Self::Raceflight),
+            2 => Some(Self::Kiss),
+            3 => Some(Self::Actual),
+            4 => Some(Self::Quick),
+            _ => None,
+        }
+    }
+}
+
+/// Rates configuration of one axis, as stored in the headers
+#[derive(Clone, Copy)]
+pub struct AxisRates {
+    pub rc_rate: f32,
+    pub expo: f32,
+    pub rate: f32,
+    pub limit: f32,
+}
+
+pub struct RatesConfig {
+    pub rates_type: RatesType,
+    pub axes: [AxisRates; 3],
+}
+
+fn power3(x: f32) -> f32 {
+    x * x * x
+}
+
+fn power5(x: f32) -> f32 {
+    x * x * x * x * x
+}
+
+impl RatesConfig {
+    pub fn from_headers(fd: &FlightData) -> Option<Self> {
+        // Betaflight only logs the rates type since 4.2, older versions always use its own
+        let rates_type = match fd.header_value::<u8>("rates_type") {
+            Some(value) => RatesType::from_header(value)?,
+            None => RatesType::Betaflight,
+        };
+        let rc_rates: Vec<f32> = fd.header_values("rc_rates")?;
+        let expo: Vec<f32> = fd.header_values("rc_expo")?;
+        let rates: Vec<f32> = fd.header_values("rates")?;
+        let limits: Vec<f32> = fd
+            .header_values("rate_limits")
+            .unwrap_or_else(|| vec![DEFAULT_RATE_LIMIT; 3]);
+
+        let axes = [0, 1, 2].map(|axis| {
+            Some(AxisRates {
+                rc_rate: *rc_rates.get(axis)?,
+                expo: *expo.get(axis)?,
+                rate: *rates.get(axis)?,
+                limit: limits.get(axis).copied().unwrap_or(DEFAULT_RATE_LIMIT),
+            })
+        });
+        let [Some(roll), Some(pitch), Some(yaw)] = axes else {
+            return None;
+        };
+
+        Some(Self {
+            rates_type,
+            axes: [roll, pitch, yaw],
+        })
+    }
+
+    /// Setpoint in °/s for a stick deflection in [-1, 1], following Betaflight's rc.c. `None`
+    /// for rates types that aren't supported.
+    pub fn setpoint(&self, axis: usize, stick: f32) -> Option<f32> {
+        let AxisRates {
+            rc_rate,
+            expo,
+            rate,
+            limit,
+        } = self.axes[axis];
+        let abs = stick.abs();
+
+        let angle_rate = match self.rates_type {
+            RatesType::Betaflight => {
+                let expof = expo / 100.0;
+                let command = stick * power3(abs) * expof + stick * (1.0 - expof);
+                let mut rc_rate = rc_rate / 100.0;
+                if rc_rate > 2.0 {
+                    rc_rate += RC_RATE_INCREMENTAL * (rc_rate - 2.0);
+                }
+                let super_factor = 1.0 / (1.0 - abs * rate / 100.0).clamp(0.01, 1.0);
+                200.0 * rc_rate * command * super_factor
+            }
+            RatesType::Actual => {
+                let expof = expo / 100.0;
+                let expof = abs * (power5(stick) * expof + stick * (1.0 - expof));
+                let center_sensitivity = rc_rate * 10.0;
+                let stick_movement = f32::max(0.0, rate * 10.0 - center_sensitivity);
+                stick * center_sensitivity + stick_movement * expof
+            }
+            RatesType::Quick if rc_rate <= 0.0 => 0.0,
+            RatesType::Quick => {
+                let rc_rate = rc_rate * 2.0;
+                let max_rate = f32::max(rate * 10.0, rc_rate);
+                let expof = expo / 100.0;
+                let super_factor_config = (max_rate / rc_rate - 1.0) / (max_rate / rc_rate);
+                let curve = power3(abs) * expof + abs * (1.0 - expof);
+                let super_factor = 1.0 / (1.0 - curve * super_factor_config).clamp(0.01, 1.0);
+                stick * rc_rate * super_factor
+            }
+            RatesType::Kiss => {
+                let curve = expo / 100.0;
+                let use_rates = 1.0 / (1.0 - abs * rate / 100.0).clamp(0.01, 1.0);
+                let command = (power3(stick) * curve + stick * (1.0 - curve)) * (rc_rate / 1000.0);
+                2000.0 * use_rates * command
+            }
+            RatesType::Raceflight => return None,
+        };
+
+        Some(angle_rate.clamp(-limit, limit))
+    }
+
+    /// (stick deflection, setpoint) pairs for absolute deflections from 0 to 1
+    pub fn curve(&self, axis: usize, points: usize) -> Option<Vec<(f64, f64)>> {
+        (0..=points)
+            .map(|i| {
+                let stick = i as f32 / points as f32;
+                let setpoint = self.setpoint(axis, stick)?;
+                Some((stick as f64, setpoint as f64))
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RatesMismatch {
+    /// The measured curve is consistently off, e.g. because of different expo
+    Curve,
+    /// The measured points scatter widely around the curve, as caused by RC smoothing lag
+    Smoothing,
+}
+
+pub struct RatesComparison {
+    /// Largest median deviation of the measured setpoint from the configured curve in °/s,
+    /// and the stick deflection at which it occurs
+    pub max_deviation: (f32, f32),
+    /// Mean absolute deviation of the samples from the median of their bin in °/s
+    pub scatter: f32,
+    pub mismatches: Vec<RatesMismatch>,
+}
+
+/// Absolute stick deflection in [0, 1] from `rcCommand`.
+pub fn stick_deflection(rc_command: f32) -> f32 {
+    (rc_command / RC_COMMAND_RANGE).abs().min(1.0)
+}
+
+/// Compares the absolute setpoints actually flown with the curve from the headers.
+pub fn compare_rates(
+    config: &RatesConfig,
+    axis: usize,
+    rc_command: &[f32],
+    setpoint: &[f32],
+) -> Option<RatesComparison> {
+    let mut bins: Vec<Vec<f32>> = vec![Vec::new(); COMPARISON_BINS];
+    for (rc, sp) in rc_command.iter().zip(setpoint.iter()) {
+        let stick = stick_deflection(*rc);
+        let expected = config.setpoint(axis, stick)?;
+        let bin = ((stick * COMPARISON_BINS as f32) as usize).min(COMPARISON_BINS - 1);
+        bins[bin].push(sp.abs() - expected);
+    }
+
+    let max_rate = config.setpoint(axis, 1.0)?.abs().max(1.0);
+    let mut max_deviation = (0.0f32, 0.0f32);
+    let mut scatter_sum = 0.0;
+    let mut scatter_count = 0;
+    for (i, bin) in bins.iter().enumerate() {
+        if bin.len() < MIN_BIN_SAMPLES {
+            continue;
+        }
+
+        let median = median(bin.iter().map(|r| *r as f64).collect()).unwrap_or_default() as f32;
+        if median.abs() > max_deviation.0.abs() {
+            max_deviation = (median, (i as f32 + 0.5) / COMPARISON_BINS as f32);
+        }
+        scatter_sum += bin.iter().map(|r| (r - median).abs()).sum::<f32>();
+        scatter_count += bin.len();
+    }
+
+    if scatter_count == 0 {
+        return None;
+    }
+
+    let scatter = scatter_sum / scatter_count as f32;
+    let mut mismatches = Vec::new();
+    if max_deviation.0.abs() > MISMATCH_THRESHOLD * max_rate {
+        mismatches.push(RatesMismatch::Curve);
+    }
+    if scatter > MISMATCH_THRESHOLD * max_rate {
+        mismatches.push(RatesMismatch::Smoothing);
+    }
+
+    Some(RatesComparison {
+        max_deviation,
+        scatter,
+        mismatches,
+    })
+}
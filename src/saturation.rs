@@ -0,0 +1,85 @@
+/// Fraction of the motor output range at either end within which a motor counts as saturated
+const SATURATION_MARGIN: f32 = 0.01;
+
+pub struct MotorSaturation {
+    /// Time in seconds the motor spent at its lowest output
+    pub at_min: f64,
+    /// Time in seconds the motor spent at its highest output
+    pub at_max: f64,
+}
+
+pub struct SaturationAnalysis {
+    pub motors: Vec<MotorSaturation>,
+    /// Time in seconds during which one motor was at its highest output while another was at
+    /// its lowest, meaning the mixer couldn't apply the full PID sum
+    pub clipping: f64,
+    /// Time in seconds spent in the air, which the figures above are relative to
+    pub duration: f64,
+    /// Upper end of the output range while any motor is saturated, zero otherwise
+    pub saturation_indicator: Vec<f32>,
+    /// Upper end of the output range while the mixer is clipping, zero otherwise
+    pub clipping_indicator: Vec<f32>,
+}
+
+/// Finds the samples where motors sit at the ends of their output range. Only samples marked
+/// in `in_flight` are counted, so the motors idling on the ground don't show up as saturated.
+pub fn detect_saturation(
+    times: &[f64],
+    motors: &[&Vec<f32>],
+    output_range: (f32, f32),
+    in_flight: &[bool],
+) -> SaturationAnalysis {
+    let (min, max) = output_range;
+    let margin = (max - min) * SATURATION_MARGIN;
+
+    let mut motor_saturation: Vec<_> = motors
+        .iter()
+        .map(|_| MotorSaturation {
+            at_min: 0.0,
+            at_max: 0.0,
+        })
+        .collect();
+    let mut clipping = 0.0;
+    let mut duration = 0.0;
+    let mut saturation_indicator = vec![0.0; times.len()];
+    let mut clipping_indicator = vec![0.0; times.len()];
+
+    for (i, dt) in times.windows(2).map(|w| w[1] - w[0]).enumerate() {
+        if !in_flight.get(i).copied().unwrap_or_default() {
+            continue;
+        }
+        duration += dt;
+
+        let mut any_min = false;
+        let mut any_max = false;
+        for (motor, saturation) in motors.iter().zip(motor_saturation.iter_mut()) {
+            let Some(value) = motor.get(i) else {
+                continue;
+            };
+
+            if *value <= min + margin {
+                saturation.at_min += dt;
+                any_min = true;
+            } else if *value >= max - margin {
+                saturation.at_max += dt;
+                any_max = true;
+            }
+        }
+
+        if any_min || any_max {
+            saturation_indicator[i] = max;
+        }
+        if any_min && any_max {
+            clipping += dt;
+            clipping_indicator[i] = max;
+        }
+    }
+
+    SaturationAnalysis {
+        motors: motor_saturation,
+        clipping,
+        duration,
+        saturation_indicator,
+        clipping_indicator,
+    }
+}
@@ -0,0 +1,118 @@
+use std::f32::consts::PI;
+
+use crate::flight_data::FlightData;
+
+/// Full-scale ranges used when the headers don't say otherwise
+const DEFAULT_GYRO_RANGE: f32 = 2000.0;
+const DEFAULT_ACCEL_RANGE: f32 = 16.0;
+/// Sensors report at most this many LSB in either direction
+const SENSOR_FULL_SCALE_LSB: f32 = 32768.0;
+/// Fraction of the full-scale range from which a sample counts as clipped
+const CLIP_FRACTION: f32 = 0.98;
+
+const STANDARD_GRAVITY: f32 = 9.80665;
+
+/// Full-scale ranges of the gyro in °/s and of the accelerometer in g.
+#[derive(Clone, Copy)]
+pub struct SensorRanges {
+    pub gyro: f32,
+    pub accel: f32,
+}
+
+impl SensorRanges {
+    /// Takes the accelerometer range from `acc_1G`. `gyro_scale` only tells the sensor range in
+    /// logs that store raw sensor LSB (scale in rad/µs per LSB), newer Betaflight logs °/s with a
+    /// scale of 1 and the usual ±2000°/s is assumed.
+    pub fn from_headers(fd: &FlightData) -> Self {
+        let accel = fd
+            .header_value::<f32>("acc_1G")
+            .filter(|one_g| *one_g > 0.0)
+            .map(|one_g| SENSOR_FULL_SCALE_LSB / one_g)
+            .unwrap_or(DEFAULT_ACCEL_RANGE);
+
+        let gyro = fd
+            .unknown_headers
+            .get("gyro_scale")
+            .and_then(|v| u32::from_str_radix(v.trim().trim_start_matches("0x"), 16).ok())
+            .map(f32::from_bits)
+            .map(|scale| SENSOR_FULL_SCALE_LSB * scale * 1_000_000.0 * 180.0 / PI)
+            .filter(|range| (250.0..=4000.0).contains(range))
+            .unwrap_or(DEFAULT_GYRO_RANGE);
+
+        Self { gyro, accel }
+    }
+}
+
+#[derive(Clone)]
+pub struct SensorClipping {
+    /// Spans of consecutive clipped samples as (start, end) in seconds
+    pub spans: Vec<(f64, f64)>,
+    pub samples: usize,
+    /// The full-scale range in the unit of the series while any axis is clipped, zero otherwise
+    pub indicator: Vec<f32>,
+}
+
+impl SensorClipping {
+    pub fn duration(&self) -> f64 {
+        self.spans.iter().map(|(start, end)| end - start).sum()
+    }
+}
+
+#[derive(Clone)]
+pub struct ClippingAnalysis {
+    pub ranges: SensorRanges,
+    pub gyro: Option<SensorClipping>,
+    pub accel: Option<SensorClipping>,
+}
+
+impl ClippingAnalysis {
+    pub fn any(&self) -> bool {
+        [&self.gyro, &self.accel]
+            .iter()
+            .any(|c| c.as_ref().is_some_and(|c| c.samples > 0))
+    }
+}
+
+fn detect(times: &[f64], axes: [&[f32]; 3], range: f32) -> SensorClipping {
+    let threshold = range * CLIP_FRACTION;
+    let len = axes.iter().map(|a| a.len()).fold(times.len(), usize::min);
+
+    let mut spans = Vec::new();
+    let mut samples = 0;
+    let mut indicator = vec![0.0; times.len()];
+    let mut span_start = None;
+    for i in 0..len {
+        let clipped = axes.iter().any(|a| a[i].abs() >= threshold);
+        if clipped {
+            samples += 1;
+            indicator[i] = range;
+            span_start.get_or_insert(times[i]);
+        } else if let Some(start) = span_start.take() {
+            spans.push((start, times[i]));
+        }
+    }
+    if let Some(start) = span_start {
+        spans.push((start, times[len - 1]));
+    }
+
+    SensorClipping {
+        spans,
+        samples,
+        indicator,
+    }
+}
+
+/// Finds samples where the raw gyro or the accelerometer sit at their full-scale range. Expects
+/// the gyro in °/s and the acceleration in m/s².
+pub fn detect_sensor_clipping(
+    times: &[f64],
+    gyro_unfiltered: Option<[&[f32]; 3]>,
+    accel: Option<[&[f32]; 3]>,
+    ranges: SensorRanges,
+) -> ClippingAnalysis {
+    ClippingAnalysis {
+        ranges,
+        gyro: gyro_unfiltered.map(|gyro| detect(times, gyro, ranges.gyro)),
+        accel: accel.map(|accel| detect(times, accel, ranges.accel * STANDARD_GRAVITY)),
+    }
+}
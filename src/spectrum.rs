@@ -0,0 +1,195 @@
+use std::f32::consts::PI;
+use std::ops::Range;
+
+use crate::iter::windows_in_ranges;
+
+/// Frequency range around a peak (in Hz) that has to be lower than the peak itself.
+const PEAK_NEIGHBOURHOOD: f64 = 25.0;
+/// How far (in dB) a peak has to rise above the lowest point of its neighbourhood.
+const PEAK_MIN_PROMINENCE: f64 = 6.0;
+
+/// Power spectral density, estimated using Welch's method. Bin `k` lies at `k * resolution` Hz,
+/// values are in (input unit)²/Hz.
+#[derive(Clone)]
+pub struct PowerSpectrum {
+    pub resolution: f64,
+    pub density: Vec<f64>,
+}
+
+impl PowerSpectrum {
+    pub fn frequency(&self, bin: usize) -> f64 {
+        (bin as f64) * self.resolution
+    }
+
+    /// RMS of the signal components with frequencies in [low, high), `None` if no bin lies in
+    /// that range.
+    pub fn band_rms(&self, low: f64, high: f64) -> Option<f64> {
+        let mut bins = self
+            .density
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (low..high).contains(&self.frequency(*i)))
+            .peekable();
+        bins.peek()?;
+        Some(bins.map(|(_, p)| p * self.resolution).sum::<f64>().sqrt())
+    }
+
+    /// (frequency, dB) pairs for plotting
+    pub fn decibels(&self) -> Vec<(f64, f64)> {
+        self.density
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (self.frequency(i), 10.0 * p.max(f64::MIN_POSITIVE).log10()))
+            .collect()
+    }
+
+    /// Local maxima that stand out from their neighbourhood, loudest first, as (frequency, dB).
+    pub fn peaks(&self, max_peaks: usize) -> Vec<(f64, f64)> {
+        let db = self.decibels();
+        let neighbourhood = usize::max(1, (PEAK_NEIGHBOURHOOD / self.resolution) as usize);
+
+        let mut peaks: Vec<_> = (1..db.len().saturating_sub(1))
+            .filter(|&i| {
+                let start = i.saturating_sub(neighbourhood);
+                let end = usize::min(i + neighbourhood + 1, db.len());
+                let value = db[i].1;
+
+                let is_max = db[start..i].iter().all(|(_, v)| *v < value)
+                    && db[i + 1..end].iter().all(|(_, v)| *v <= value);
+                let floor = db[start..end]
+                    .iter()
+                    .fold(f64::INFINITY, |min, (_, v)| f64::min(min, *v));
+
+                is_max && value - floor >= PEAK_MIN_PROMINENCE
+            })
+            .map(|i| db[i])
+            .collect();
+
+        peaks.sort_by(|a, b| b.1.total_cmp(&a.1));
+        peaks.truncate(max_peaks);
+        peaks
+    }
+}
+
+fn hann_window(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|i| 0.5 * (1.0 - (2.0 * PI * (i as f32) / (size as f32)).cos()))
+        .collect()
+}
+
+/// Estimates the one-sided power spectral density of `data` by averaging the periodograms of
+/// overlapping, Hann-windowed segments of `segment_size` samples. Segments are only taken from
+/// within `ranges`. Returns `None` if no range is as long as a single segment.
+pub fn welch_psd(
+    data: &[f32],
+    ranges: &[Range<usize>],
+    sample_rate: f64,
+    segment_size: usize,
+) -> Option<PowerSpectrum> {
+    let window = hann_window(segment_size);
+    let window_power: f64 = window.iter().map(|w| (*w as f64).powi(2)).sum();
+
+    let fft = realfft::RealFftPlanner::<f32>::new().plan_fft_forward(segment_size);
+    let mut input = fft.make_input_vec();
+    let mut output = fft.make_output_vec();
+
+    let mut density = vec![0.0; output.len()];
+    let mut count = 0;
+    for range in windows_in_ranges(ranges, data.len(), segment_size, segment_size / 2) {
+        let segment = &data[range];
+        let mean = segment.iter().sum::<f32>() / (segment_size as f32);
+        for ((i, x), w) in input.iter_mut().zip(segment.iter()).zip(window.iter()) {
+            *i = (x - mean) * w;
+        }
+
+        fft.process(&mut input, &mut output).ok()?;
+        for (d, c) in density.iter_mut().zip(output.iter()) {
+            *d += c.norm_sqr() as f64;
+        }
+        count += 1;
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    // Scale to a density and fold the negative frequencies (everything but DC and Nyquist)
+    // into the one-sided spectrum.
+    let scale = 1.0 / (sample_rate * window_power * (count as f64));
+    let last = density.len() - 1;
+    let nyquist = (last * 2 == segment_size).then_some(last);
+    for (k, d) in density.iter_mut().enumerate() {
+        *d *= scale;
+        if k != 0 && Some(k) != nyquist {
+            *d *= 2.0;
+        }
+    }
+
+    Some(PowerSpectrum {
+        resolution: sample_rate / (segment_size as f64),
+        density,
+    })
+}
+
+/// Magnitude-squared coherence between `x` and `y` as (frequency, coherence) pairs, from the
+/// same overlapping, Hann-windowed segments within `ranges` as `welch_psd`. A coherence of 1
+/// means that `y` depends linearly on `x` at that frequency, 0 that they are unrelated.
+pub fn coherence(
+    x: &[f32],
+    y: &[f32],
+    ranges: &[Range<usize>],
+    sample_rate: f64,
+    segment_size: usize,
+) -> Option<Vec<(f64, f64)>> {
+    let window = hann_window(segment_size);
+    let fft = realfft::RealFftPlanner::<f32>::new().plan_fft_forward(segment_size);
+    let mut input = fft.make_input_vec();
+    let mut output_x = fft.make_output_vec();
+    let mut output_y = fft.make_output_vec();
+
+    let bins = output_x.len();
+    let mut pxx = vec![0.0f64; bins];
+    let mut pyy = vec![0.0f64; bins];
+    let mut pxy = vec![(0.0f64, 0.0f64); bins];
+    let mut count = 0;
+    let len = usize::min(x.len(), y.len());
+    for range in windows_in_ranges(ranges, len, segment_size, segment_size / 2) {
+        for (data, output) in [(x, &mut output_x), (y, &mut output_y)] {
+            let segment = &data[range.clone()];
+            let mean = segment.iter().sum::<f32>() / (segment_size as f32);
+            for ((i, v), w) in input.iter_mut().zip(segment.iter()).zip(window.iter()) {
+                *i = (v - mean) * w;
+            }
+            fft.process(&mut input, output).ok()?;
+        }
+
+        for k in 0..bins {
+            let (cx, cy) = (output_x[k], output_y[k]);
+            pxx[k] += cx.norm_sqr() as f64;
+            pyy[k] += cy.norm_sqr() as f64;
+            let cross = cx.conj() * cy;
+            pxy[k].0 += cross.re as f64;
+            pxy[k].1 += cross.im as f64;
+        }
+        count += 1;
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    let resolution = sample_rate / (segment_size as f64);
+    Some(
+        (0..bins)
+            .map(|k| {
+                let denominator = pxx[k] * pyy[k];
+                let value = if denominator > 0.0 {
+                    (pxy[k].0.powi(2) + pxy[k].1.powi(2)) / denominator
+                } else {
+                    0.0
+                };
+                ((k as f64) * resolution, value)
+            })
+            .collect(),
+    )
+}
@@ -1,5 +1,10 @@
+use std::ops::Range;
+
 use realfft::num_complex::Complex32;
 
+use crate::iter::windows_in_ranges;
+use crate::utils::median;
+
 fn fft_forward(data: &[f32]) -> Vec<Complex32> {
     let mut input = data.to_vec();
     let planner = realfft::RealFftPlanner::<f32>::new().plan_fft_forward(input.len());
@@ -58,3 +63,238 @@ pub fn calculate_step_response(
         .map(|(t, s)| (*t - start, s as f64))
         .collect()
 }
+
+/// Step response averaged over the `ranges` of the flight, weighted by their length. Each range
+/// is deconvolved on its own, so the response doesn't pick up the steps between them. Ranges too
+/// short for the full response are skipped.
+pub fn average_step_response(
+    times: &[f64],
+    setpoint: &[f32],
+    gyro_filtered: &[f32],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> Vec<(f64, f64)> {
+    let len = times.len().min(setpoint.len()).min(gyro_filtered.len());
+    let responses: Vec<(usize, Vec<(f64, f64)>)> = ranges
+        .iter()
+        .map(|range| range.start.min(len)..range.end.min(len))
+        .filter(|range| range.len() >= 2)
+        .map(|range| {
+            let response = calculate_step_response(
+                &times[range.clone()],
+                &setpoint[range.clone()],
+                &gyro_filtered[range.clone()],
+                sample_rate,
+            );
+            (range.len(), response)
+        })
+        .filter(|(_, response)| response.iter().all(|(_, y)| y.is_finite()))
+        .collect();
+
+    let response_len = responses.iter().map(|(_, r)| r.len()).max().unwrap_or(0);
+    let mut sum = vec![(0.0, 0.0); response_len];
+    let mut total_weight = 0.0;
+    for (weight, response) in responses.iter().filter(|(_, r)| r.len() == response_len) {
+        for (sum, (t, y)) in sum.iter_mut().zip(response.iter()) {
+            *sum = (*t, sum.1 + y * *weight as f64);
+        }
+        total_weight += *weight as f64;
+    }
+
+    sum.into_iter()
+        .map(|(t, y)| (t, y / total_weight))
+        .collect()
+}
+
+/// Length of the windows the flight is cut into for band-wise step responses.
+const BAND_WINDOW_DURATION: f64 = 1.0;
+/// Windows with less stick input than this (in °/s) don't excite the system enough to give a
+/// meaningful response, so they are skipped.
+const BAND_MIN_EXCITATION: f32 = 20.0;
+
+/// Half-overlapping windows of `window_size` samples over `signal`, within `ranges`, in which
+/// its absolute value reaches `min_excitation`, i.e. in which there was enough input to analyse
+/// the response.
+pub fn excited_windows<'a>(
+    signal: &'a [f32],
+    ranges: &'a [Range<usize>],
+    window_size: usize,
+    min_excitation: f32,
+) -> impl Iterator<Item = Range<usize>> + 'a {
+    let window_size = if window_size < 2 { 0 } else { window_size };
+    windows_in_ranges(ranges, signal.len(), window_size, window_size / 2).filter(move |range| {
+        signal[range.clone()]
+            .iter()
+            .any(|x| x.abs() >= min_excitation)
+    })
+}
+
+/// The excited windows of `BAND_WINDOW_DURATION` over the setpoint, each with the index of the
+/// half-open band in `bands` that `classify` puts it into, if any.
+fn band_windows<'a, F>(
+    setpoint: &'a [f32],
+    ranges: &'a [Range<usize>],
+    sample_rate: f64,
+    bands: &'a [(f32, f32)],
+    classify: F,
+) -> impl Iterator<Item = (Range<usize>, Option<usize>)> + 'a
+where
+    F: Fn(Range<usize>) -> f32 + 'a,
+{
+    let window_size = (sample_rate * BAND_WINDOW_DURATION) as usize;
+    excited_windows(setpoint, ranges, window_size, BAND_MIN_EXCITATION).map(move |range| {
+        let value = classify(range.clone());
+        let band = bands
+            .iter()
+            .position(|(low, high)| value >= *low && value < *high);
+        (range, band)
+    })
+}
+
+/// Calculates step responses for overlapping windows within the `ranges` of the flight and
+/// averages them per band.
+/// Each window is assigned to a band by passing its sample range to `classify` and checking
+/// which of the half-open `bands` the result falls into. Bands without any windows are `None`.
+pub fn calculate_step_response_bands<F>(
+    times: &[f64],
+    setpoint: &[f32],
+    gyro_filtered: &[f32],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+    bands: &[(f32, f32)],
+    classify: F,
+) -> Vec<Option<Vec<(f64, f64)>>>
+where
+    F: Fn(Range<usize>) -> f32,
+{
+    let len = usize::min(setpoint.len(), gyro_filtered.len());
+    let mut sums: Vec<Vec<(f64, f64)>> = vec![Vec::new(); bands.len()];
+    let mut counts = vec![0usize; bands.len()];
+
+    for (range, band) in band_windows(&setpoint[..len], ranges, sample_rate, bands, classify) {
+        let Some(band) = band else {
+            continue;
+        };
+
+        let response = calculate_step_response(
+            &times[range.clone()],
+            &setpoint[range.clone()],
+            &gyro_filtered[range],
+            sample_rate,
+        );
+        if response.iter().any(|(_, y)| !y.is_finite()) {
+            continue;
+        }
+
+        if counts[band] == 0 {
+            sums[band] = response;
+        } else {
+            for (sum, (_, y)) in sums[band].iter_mut().zip(response.iter()) {
+                sum.1 += y;
+            }
+        }
+        counts[band] += 1;
+    }
+
+    sums.into_iter()
+        .zip(counts)
+        .map(|(sum, count)| {
+            (count > 0).then(|| {
+                sum.into_iter()
+                    .map(|(t, y)| (t, y / (count as f64)))
+                    .collect()
+            })
+        })
+        .collect()
+}
+
+/// Longest delay between setpoint and gyro that is searched for, in seconds.
+const MAX_LATENCY: f64 = 0.1;
+
+/// Delay of `gyro_filtered` behind `setpoint` in seconds, from the peak of the cross-correlation
+/// of their first differences. Differencing sharpens the peak, since stick inputs are mostly slow.
+fn window_latency(setpoint: &[f32], gyro_filtered: &[f32], sample_rate: f64) -> Option<f64> {
+    let max_lag = (MAX_LATENCY * sample_rate) as usize;
+    let ds: Vec<f32> = setpoint.windows(2).map(|w| w[1] - w[0]).collect();
+    let dg: Vec<f32> = gyro_filtered.windows(2).map(|w| w[1] - w[0]).collect();
+    if ds.len() <= max_lag + 2 {
+        return None;
+    }
+
+    let correlation: Vec<f64> = (0..=max_lag)
+        .map(|lag| {
+            // normalize by the overlap, otherwise shorter lags are favoured
+            let overlap = ds.len() - lag;
+            ds[..overlap]
+                .iter()
+                .zip(dg[lag..].iter())
+                .map(|(s, g)| (*s as f64) * (*g as f64))
+                .sum::<f64>()
+                / overlap as f64
+        })
+        .collect();
+
+    let (peak, value) = correlation
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    if *value <= 0.0 || !value.is_finite() {
+        return None;
+    }
+
+    // refine the peak position by fitting a parabola through it and its neighbours
+    let offset = if peak > 0 && peak < max_lag {
+        let (left, right) = (correlation[peak - 1], correlation[peak + 1]);
+        let denominator = left - 2.0 * value + right;
+        if denominator != 0.0 {
+            0.5 * (left - right) / denominator
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    };
+
+    Some((peak as f64 + offset) / sample_rate)
+}
+
+/// Delay from setpoint to gyro in seconds, overall and per band.
+pub struct Latency {
+    pub overall: Option<f64>,
+    pub bands: Vec<Option<f64>>,
+}
+
+/// Estimates the latency from the windows with stick input within `ranges`, using the median over
+/// all windows (of a band) to ignore windows in which the pilot's inputs didn't correlate well.
+/// Windows are assigned to bands the same way as in `calculate_step_response_bands`.
+pub fn calculate_latency<F>(
+    setpoint: &[f32],
+    gyro_filtered: &[f32],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+    bands: &[(f32, f32)],
+    classify: F,
+) -> Latency
+where
+    F: Fn(Range<usize>) -> f32,
+{
+    let len = usize::min(setpoint.len(), gyro_filtered.len());
+    let mut all = Vec::new();
+    let mut per_band = vec![Vec::new(); bands.len()];
+    for (range, band) in band_windows(&setpoint[..len], ranges, sample_rate, bands, classify) {
+        let Some(latency) =
+            window_latency(&setpoint[range.clone()], &gyro_filtered[range], sample_rate)
+        else {
+            continue;
+        };
+        all.push(latency);
+        if let Some(band) = band {
+            per_band[band].push(latency);
+        }
+    }
+
+    Latency {
+        overall: median(all),
+        bands: per_band.into_iter().map(median).collect(),
+    }
+}
@@ -0,0 +1,190 @@
+use std::ops::Range;
+
+use crate::iter::values_in_ranges;
+
+/// `rcCommand` range of roll, pitch and yaw, in both directions around center
+const RC_COMMAND_RANGE: f32 = 500.0;
+/// `rcCommand` range of throttle
+const RC_THROTTLE_MIN: f32 = 1000.0;
+const RC_THROTTLE_MAX: f32 = 2000.0;
+
+/// Heatmaps have `HEATMAP_BINS` × `HEATMAP_BINS` cells over ±100% deflection
+pub const HEATMAP_BINS: usize = 50;
+/// Deflection histograms cover ±100% in bins of `DEFLECTION_BIN_WIDTH` %
+pub const DEFLECTION_BIN_WIDTH: f32 = 5.0;
+/// Stick speed histograms cover 0 to `STICK_SPEED_RANGE` %/s in bins of `STICK_SPEED_BIN_WIDTH`
+pub const STICK_SPEED_RANGE: f32 = 2000.0;
+pub const STICK_SPEED_BIN_WIDTH: f32 = 50.0;
+/// Throttle time distribution in bins of `THROTTLE_BIN_WIDTH` %
+pub const THROTTLE_BIN_WIDTH: f32 = 5.0;
+/// Stick speed is measured over this window to not be dominated by the RC packet steps
+const STICK_SPEED_WINDOW: f64 = 0.02;
+/// Deflection above which a stick counts as fully deflected, in %
+const FULL_DEFLECTION: f32 = 95.0;
+
+/// 2D histogram of the position of one stick, both axes in % from -100 to 100.
+pub struct Heatmap {
+    /// Share of samples in %, row-major with `HEATMAP_BINS` cells per row, starting bottom left
+    pub cells: Vec<f32>,
+    pub max: f32,
+}
+
+impl Heatmap {
+    fn new(x: &[f32], y: &[f32]) -> Self {
+        let mut counts = vec![0usize; HEATMAP_BINS * HEATMAP_BINS];
+        for (x, y) in x.iter().zip(y.iter()) {
+            let col = bin(*x + 100.0, 200.0 / HEATMAP_BINS as f32, HEATMAP_BINS);
+            let row = bin(*y + 100.0, 200.0 / HEATMAP_BINS as f32, HEATMAP_BINS);
+            counts[row * HEATMAP_BINS + col] += 1;
+        }
+
+        let cells = shares(counts, x.len());
+        let max = cells.iter().copied().fold(0.0, f32::max);
+        Self { cells, max }
+    }
+}
+
+pub struct AxisUsage {
+    /// Share of samples per deflection bin in %, starting at -100%
+    pub deflection: Vec<f32>,
+    /// Share of samples per stick speed bin in %, starting at 0. Faster movements are counted in
+    /// the last bin.
+    pub speed: Vec<f32>,
+    pub mean_deflection: f32,
+    /// Share of time spent at (almost) full deflection in %
+    pub full_deflection: f32,
+    /// 95th percentile of the stick speed in %/s
+    pub speed_p95: f32,
+}
+
+pub struct StickUsage {
+    /// Yaw against throttle, for mode 2 radios
+    pub left: Heatmap,
+    /// Roll against pitch, for mode 2 radios
+    pub right: Heatmap,
+    /// Roll, pitch and yaw
+    pub axes: [AxisUsage; 3],
+    /// Time spent in each throttle bin in seconds, starting at 0%
+    pub throttle_time: Vec<f64>,
+    pub mean_throttle: f32,
+}
+
+fn bin(value: f32, width: f32, count: usize) -> usize {
+    ((value / width).floor() as isize).clamp(0, count as isize - 1) as usize
+}
+
+fn shares(counts: Vec<usize>, total: usize) -> Vec<f32> {
+    let total = total.max(1) as f32;
+    counts
+        .into_iter()
+        .map(|c| 100.0 * c as f32 / total)
+        .collect()
+}
+
+fn histogram(values: &[f32], offset: f32, width: f32, count: usize) -> Vec<f32> {
+    let mut counts = vec![0usize; count];
+    for v in values.iter().filter(|v| v.is_finite()) {
+        counts[bin(v + offset, width, count)] += 1;
+    }
+    shares(counts, values.len())
+}
+
+/// Absolute rate of change of the deflection in %/s, over `STICK_SPEED_WINDOW`.
+fn stick_speed(times: &[f64], deflection: &[f32], sample_rate: f64) -> Vec<f32> {
+    let window = usize::max(1, (STICK_SPEED_WINDOW * sample_rate).round() as usize);
+    (window..deflection.len().min(times.len()))
+        .filter_map(|i| {
+            let dt = times[i] - times[i - window];
+            (dt > 0.0).then(|| ((deflection[i] - deflection[i - window]) as f64 / dt).abs() as f32)
+        })
+        .collect()
+}
+
+fn percentile(values: &[f32], p: f32) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let index = ((sorted.len() - 1) as f32 * p).round() as usize;
+    sorted[index]
+}
+
+fn axis_usage(
+    times: &[f64],
+    deflection: &[f32],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> AxisUsage {
+    let deflection_bins = (200.0 / DEFLECTION_BIN_WIDTH) as usize;
+    let speed_bins = (STICK_SPEED_RANGE / STICK_SPEED_BIN_WIDTH) as usize;
+    let len = deflection.len().min(times.len());
+    // per range, so the jumps between them don't count as stick movements
+    let speed: Vec<f32> = ranges
+        .iter()
+        .map(|range| range.start.min(len)..range.end.min(len))
+        .flat_map(|range| stick_speed(&times[range.clone()], &deflection[range], sample_rate))
+        .collect();
+    let deflection = values_in_ranges(deflection, ranges);
+    let len = deflection.len().max(1) as f32;
+
+    AxisUsage {
+        deflection: histogram(&deflection, 100.0, DEFLECTION_BIN_WIDTH, deflection_bins),
+        speed: histogram(&speed, 0.0, STICK_SPEED_BIN_WIDTH, speed_bins),
+        mean_deflection: deflection.iter().map(|d| d.abs()).sum::<f32>() / len,
+        full_deflection: 100.0
+            * deflection
+                .iter()
+                .filter(|d| d.abs() >= FULL_DEFLECTION)
+                .count() as f32
+            / len,
+        speed_p95: percentile(&speed, 0.95),
+    }
+}
+
+/// Analyzes how the sticks were used from `rcCommand` within `ranges`, with deflections in %.
+pub fn analyze_stick_usage(
+    times: &[f64],
+    rc_command: [&[f32]; 4],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> StickUsage {
+    let [roll, pitch, yaw] = [0, 1, 2].map(|axis| {
+        rc_command[axis]
+            .iter()
+            .map(|rc| (100.0 * rc / RC_COMMAND_RANGE).clamp(-100.0, 100.0))
+            .collect::<Vec<f32>>()
+    });
+    let throttle: Vec<f32> = rc_command[3]
+        .iter()
+        .map(|rc| {
+            (100.0 * (rc - RC_THROTTLE_MIN) / (RC_THROTTLE_MAX - RC_THROTTLE_MIN)).clamp(0.0, 100.0)
+        })
+        .collect();
+
+    let throttle_bins = (100.0 / THROTTLE_BIN_WIDTH) as usize;
+    let mut throttle_time = vec![0.0; throttle_bins];
+    let len = times.len().min(throttle.len());
+    for i in ranges
+        .iter()
+        .flat_map(|range| range.start..range.end.min(len).saturating_sub(1))
+    {
+        throttle_time[bin(throttle[i], THROTTLE_BIN_WIDTH, throttle_bins)] +=
+            times[i + 1] - times[i];
+    }
+
+    let axes = [&roll, &pitch, &yaw].map(|d| axis_usage(times, d, sample_rate, ranges));
+    let [roll, pitch, yaw, throttle] =
+        [&roll, &pitch, &yaw, &throttle].map(|values| values_in_ranges(values, ranges));
+
+    // The heatmaps show throttle over the full height, like the stick on the radio
+    let throttle_centered: Vec<f32> = throttle.iter().map(|t| 2.0 * t - 100.0).collect();
+
+    StickUsage {
+        left: Heatmap::new(&yaw, &throttle_centered),
+        right: Heatmap::new(&roll, &pitch),
+        axes,
+        throttle_time,
+        mean_throttle: throttle.iter().sum::<f32>() / throttle.len().max(1) as f32,
+    }
+}
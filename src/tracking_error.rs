@@ -0,0 +1,90 @@
+use std::ops::Range;
+
+use crate::iter::values_in_ranges;
+use crate::utils::rms;
+
+/// Error histograms cover ±`HISTOGRAM_RANGE` °/s in bins of `HISTOGRAM_BIN_WIDTH` °/s
+pub const HISTOGRAM_RANGE: f32 = 100.0;
+pub const HISTOGRAM_BIN_WIDTH: f32 = 2.0;
+
+pub struct TrackingError {
+    /// Setpoint minus filtered gyro in °/s
+    pub error: Vec<f32>,
+    pub rms: f32,
+    /// Share of samples in each histogram bin in %, starting at `-HISTOGRAM_RANGE`. Samples
+    /// outside of the histogram range are counted in the outermost bins.
+    pub histogram: Vec<f32>,
+    /// RMS error per throttle band, `None` for bands the flight never entered
+    pub throttle_bands: Vec<Option<f32>>,
+    /// RMS error per stick rate band, by absolute setpoint of the same axis
+    pub stick_rate_bands: Vec<Option<f32>>,
+}
+
+fn band_rms(error: &[f32], classifier: &[f32], bands: &[(f32, f32)]) -> Vec<Option<f32>> {
+    bands
+        .iter()
+        .map(|(low, high)| {
+            rms(error
+                .iter()
+                .zip(classifier.iter())
+                .filter(|(_, c)| (*low..*high).contains(*c))
+                .map(|(e, _)| *e))
+        })
+        .collect()
+}
+
+fn histogram(error: &[f32]) -> Vec<f32> {
+    let bin_count = (2.0 * HISTOGRAM_RANGE / HISTOGRAM_BIN_WIDTH) as usize;
+    let mut bins = vec![0usize; bin_count];
+    for e in error.iter().filter(|e| e.is_finite()) {
+        let bin = ((e + HISTOGRAM_RANGE) / HISTOGRAM_BIN_WIDTH).floor() as isize;
+        bins[bin.clamp(0, bin_count as isize - 1) as usize] += 1;
+    }
+
+    let total = error.len().max(1) as f32;
+    bins.into_iter().map(|b| 100.0 * b as f32 / total).collect()
+}
+
+/// Compares the filtered gyro against the setpoint of one axis. The error is calculated for the
+/// whole flight, the statistics only over the samples within `ranges`.
+pub fn calculate_tracking_error(
+    setpoint: &[f32],
+    gyro_filtered: &[f32],
+    throttle: &[f32],
+    ranges: &[Range<usize>],
+    throttle_bands: &[(f32, f32)],
+    stick_rate_bands: &[(f32, f32)],
+) -> TrackingError {
+    let error: Vec<f32> = setpoint
+        .iter()
+        .zip(gyro_filtered.iter())
+        .map(|(s, g)| s - g)
+        .collect();
+    let selected = values_in_ranges(&error, ranges);
+    let throttle = values_in_ranges(throttle, ranges);
+    let stick_rate: Vec<f32> = values_in_ranges(setpoint, ranges)
+        .iter()
+        .map(|s| s.abs())
+        .collect();
+
+    TrackingError {
+        rms: rms(selected.iter().copied()).unwrap_or_default(),
+        histogram: histogram(&selected),
+        throttle_bands: band_rms(&selected, &throttle, throttle_bands),
+        stick_rate_bands: band_rms(&selected, &stick_rate, stick_rate_bands),
+        error,
+    }
+}
+
+/// Combined RMS over several axes, as a single figure for how well the quad follows the sticks.
+pub fn combined_rms(axes: &[&TrackingError]) -> f32 {
+    let (sum, count) = axes.iter().fold((0.0f64, 0usize), |(sum, count), axis| {
+        let len = axis.error.len();
+        (sum + (axis.rms as f64).powi(2) * len as f64, count + len)
+    });
+    if count == 0 {
+        return 0.0;
+    }
+
+    (sum / count as f64).sqrt() as f32
+}
@@ -11,6 +11,38 @@ pub fn execute_in_background<F: Future<Output = ()> + 'static>(f: F) {
     wasm_bindgen_futures::spawn_local(f);
 }
 
+/// Asks the user where to save a file and writes `contents` to it in the background.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(file_name: String, contents: String) {
+    execute_in_background(async move {
+        let Some(file) = rfd::AsyncFileDialog::new()
+            .set_file_name(file_name)
+            .save_file()
+            .await
+        else {
+            return;
+        };
+
+        if let Err(e) = file.write(contents.as_bytes()).await {
+            log::error!("Failed to write {:?}: {}", file.path(), e);
+        }
+    });
+}
+
+/// Root mean square of `values`, `None` if there are none.
+pub fn rms(values: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.fold((0.0f64, 0usize), |(sum, count), v| {
+        (sum + (v as f64).powi(2), count + 1)
+    });
+    (count > 0).then(|| (sum / count as f64).sqrt() as f32)
+}
+
+/// Median of `values` (the upper one for an even count), `None` if there are none.
+pub fn median(mut values: Vec<f64>) -> Option<f64> {
+    values.sort_by(|a, b| a.total_cmp(b));
+    values.get(values.len() / 2).copied()
+}
+
 pub struct BackgroundCompStore<C> {
     data: Option<C>,
     receiver: Receiver<C>,
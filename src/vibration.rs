@@ -0,0 +1,47 @@
+use std::ops::Range;
+
+use crate::filters::band_pass;
+use crate::iter::windows_in_ranges;
+
+/// Frequency band of the frame vibrations that disturb the accelerometer
+pub const VIBRATION_BAND: (f64, f64) = (50.0, 500.0);
+/// Length of the windows over which the vibration level is averaged
+const WINDOW_DURATION: f64 = 0.1;
+const STANDARD_GRAVITY: f64 = 9.80665;
+
+/// Combined RMS of the band-passed accelerometer axes in g, as (time, g) per window within
+/// `ranges`. Expects the acceleration in m/s².
+pub fn vibration_level(
+    times: &[f64],
+    accel: [&[f32]; 3],
+    sample_rate: f64,
+    ranges: &[Range<usize>],
+) -> Vec<(f64, f64)> {
+    if sample_rate <= 0.0 {
+        return Vec::new();
+    }
+
+    let filtered = accel.map(|a| band_pass(a, sample_rate, VIBRATION_BAND.0, VIBRATION_BAND.1));
+    let len = filtered
+        .iter()
+        .map(|f| f.len())
+        .min()
+        .unwrap_or_default()
+        .min(times.len());
+    let window = usize::max(1, (WINDOW_DURATION * sample_rate) as usize);
+
+    windows_in_ranges(ranges, len, window, window)
+        .map(|Range { start, end }| {
+            let sum_of_squares: f64 = filtered
+                .iter()
+                .flat_map(|f| f[start..end].iter())
+                .map(|v| (*v as f64).powi(2))
+                .sum();
+            let rms = (sum_of_squares / (end - start) as f64).sqrt();
+            (
+                (times[start] + times[end - 1]) / 2.0,
+                rms / STANDARD_GRAVITY,
+            )
+        })
+        .collect()
+}